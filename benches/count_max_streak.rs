@@ -0,0 +1,36 @@
+//! Times `count_max_streak` on large inline code contents
+//!
+//! Run with `cargo bench --bench count_max_streak`
+
+use markdown_gen::markdown::{AsMarkdown, MarkdownWritable, RenderContext, RenderOptions};
+use std::hint::black_box;
+use std::time::Instant;
+
+fn bench(name: &str, text: &str, iterations: u32) {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(black_box(text).count_max_streak(b'`', 0));
+    }
+    let elapsed = start.elapsed() / iterations;
+    let throughput = text.len() as f64 / elapsed.as_secs_f64() / 1e9;
+    println!("{:<24} {:>12?} {:>8.2} GB/s", name, elapsed, throughput);
+}
+
+fn main() {
+    let plain = "fn main() { println!(\"hello\"); }\n".repeat(100_000);
+    let sparse = "let x = `a`;\n".repeat(250_000);
+    let dense = "``x".repeat(1_000_000);
+
+    bench("no backticks (3 MB)", &plain, 50);
+    bench("sparse backticks (3 MB)", &sparse, 50);
+    bench("dense backticks (3 MB)", &dense, 50);
+
+    let line = sparse.replace('\n', " ");
+    let mut out = Vec::with_capacity(line.len() + 16);
+    let start = Instant::now();
+    line.as_str()
+        .code()
+        .write_to(&mut RenderContext::new(&mut out, &RenderOptions::new()).with_inner(true))
+        .unwrap();
+    println!("{:<24} {:>12?}", "code() write (3 MB)", start.elapsed());
+}
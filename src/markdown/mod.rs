@@ -1,22 +1,391 @@
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
 use std::io;
 use std::io::{Error, Write};
-use Escaping::{InlineCode, Normal};
+use Escaping::{Html, InlineCode, Normal};
 
 #[cfg(test)]
 mod tests;
 
 /// Specifies string escaping mode
+///
+/// More modes may be added in minor releases, so matches outside this crate
+/// need a wildcard arm:
+/// ```compile_fail
+/// use markdown_gen::markdown::Escaping;
+///
+/// fn name(escaping: Escaping) -> &'static str {
+///     match escaping {
+///         Escaping::Normal => "normal",
+///         Escaping::InlineCode => "inline code",
+///         Escaping::Html => "html",
+///     }
+/// }
+/// ```
 #[derive(Clone, Copy)]
+#[non_exhaustive]
 pub enum Escaping {
-    /// `` \`*_{}[]()#+-.!`` will be escaped with a backslash
+    /// Characters of [RenderOptions::escape_set](struct.RenderOptions.html#method.escape_set)
+    /// will be escaped with a backslash and so will `>=~<+-` at the start of
+    /// a line, `.` or `)` following a number there, `!` before `[`,
+    /// including a link following the text, and `&` that could start an
+    /// entity such as `&amp;`
     Normal,
     /// Inline code will be surrounded by enough backticks to escape the contents
     InlineCode,
+    /// `&<>"'` will be replaced with HTML entities and inline elements such
+    /// as [RichText](struct.RichText.html) and [Link](struct.Link.html) are
+    /// written as HTML tags
+    Html,
+    /// Strings are written verbatim, see [Trusted](struct.Trusted.html)
+    None,
+}
+
+/// Formatting choices applied to a whole document
+///
+/// Elements fall back to these settings unless they were configured
+/// individually, e.g. with [List::bullet](struct.List.html#method.bullet).
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    compact: bool,
+    bullet: u8,
+    emphasis: u8,
+    indent: usize,
+    escape_set: Vec<u8>,
+}
+
+impl RenderOptions {
+    /// Creates the default options: blank lines between blocks, `*` bullets
+    /// and emphasis, lists indented by 3 spaces
+    pub fn new() -> Self {
+        Self {
+            compact: false,
+            bullet: b'*',
+            emphasis: b'*',
+            indent: 3,
+            escape_set: b"\\`*_{}[]#".to_vec(),
+        }
+    }
+
+    /// Separates blocks by a single newline instead of a blank line wherever
+    /// that doesn't change their meaning
+    ///
+    /// That is after a block which can't continue on the next line, such as
+    /// a heading, a code block or a thematic break, and before a heading or a
+    /// code block, which end any block but HTML. Elsewhere a single newline
+    /// would merge the blocks, so they are still separated by a blank line.
+    pub fn compact(mut self, compact: bool) -> Self {
+        self.compact = compact;
+        self
+    }
+
+    /// Sets the marker used by bulleted lists
+    ///
+    /// # Arguments
+    /// * `marker` - One of `-`, `*` or `+`
+    pub fn bullet(mut self, marker: char) -> Self {
+        assert!(
+            matches!(marker, '-' | '*' | '+'),
+            "List bullet must be one of '-', '*' or '+'."
+        );
+        self.bullet = marker as u8;
+        self
+    }
+
+    /// Sets the character used for **bold** and *italic* markers
+    ///
+    /// # Arguments
+    /// * `marker` - Either `'*'` or `'_'`
+    pub fn emphasis(mut self, marker: char) -> Self {
+        assert!(
+            marker == '*' || marker == '_',
+            "Emphasis marker must be '*' or '_'."
+        );
+        self.emphasis = marker as u8;
+        self
+    }
+
+    /// Sets how many spaces each nesting level of a list is indented by
+    ///
+    /// See [List::indent](struct.List.html#method.indent).
+    ///
+    /// # Arguments
+    /// * `spaces` - Indentation width (1-4)
+    pub fn indent(mut self, spaces: usize) -> Self {
+        assert!(spaces > 0 && spaces <= 4, "List indent must be range 1-4.");
+        self.indent = spaces;
+        self
+    }
+
+    /// Sets the characters escaped with a backslash anywhere in text
+    ///
+    /// Defaults to `` \`*_{}[]#``. Characters which only start a block at the
+    /// beginning of a line, or an image before `[`, are escaped there
+    /// regardless, see [Escaping::Normal](enum.Escaping.html#variant.Normal).
+    /// Leaving out characters which are Markdown syntax in the target dialect
+    /// lets text be parsed as formatting, `` \`*_{}[]()#+-.!`` escapes all punctuation
+    /// CommonMark could give a meaning to.
+    ///
+    /// # Arguments
+    /// * `set` - ASCII punctuation characters to escape
+    pub fn escape_set(mut self, set: &[u8]) -> Self {
+        assert!(
+            set.iter().all(u8::is_ascii_punctuation),
+            "Escape set must only contain ASCII punctuation."
+        );
+        self.escape_set = set.to_vec();
+        self
+    }
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Where the text written so far leaves the current line
+///
+/// Decides whether the next character could start a block.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum LineState {
+    /// Only indentation or quote markers since the line break
+    Start,
+    /// Digits at the start of a line, which may become a list number
+    Digits(usize),
+    /// Any other text
+    Text,
+}
+
+impl LineState {
+    fn next(self, c: u8) -> Self {
+        match (self, c) {
+            (_, b'\n' | b'\r') => LineState::Start,
+            (LineState::Start, b' ' | b'\t' | b'>') => LineState::Start,
+            (LineState::Start, b'0'..=b'9') => LineState::Digits(1),
+            (LineState::Digits(n), b'0'..=b'9') => LineState::Digits(n + 1),
+            _ => LineState::Text,
+        }
+    }
+}
+
+/// State passed to [MarkdownWritable::write_to](trait.MarkdownWritable.html#tymethod.write_to)
+///
+/// Bundles the destination writer with everything describing where the
+/// element is written. Output goes through its `std::io::Write` implementation.
+pub struct RenderContext<'a> {
+    writer: &'a mut dyn Write,
+    inner: bool,
+    escape: Escaping,
+    line_prefix: Option<&'a [u8]>,
+    options: &'a RenderOptions,
+    line: Cow<'a, Cell<LineState>>,
+    before_bracket: bool,
+    document_start: bool,
+    list_item: Option<(&'a [u8], usize)>,
+}
+
+impl<'a> RenderContext<'a> {
+    /// Creates a context for a top-level element with [Normal](enum.Escaping.html#variant.Normal)
+    /// escaping and no line prefix
+    ///
+    /// # Arguments
+    /// * `writer` - Destination writer
+    /// * `options` - Formatting choices, see [RenderOptions](struct.RenderOptions.html)
+    pub fn new(writer: &'a mut dyn Write, options: &'a RenderOptions) -> Self {
+        Self {
+            writer,
+            inner: false,
+            escape: Normal,
+            line_prefix: None,
+            options,
+            line: Cow::Owned(Cell::new(LineState::Start)),
+            before_bracket: false,
+            document_start: false,
+            list_item: None,
+        }
+    }
+
+    /// Returns a context for a child element, writing to the same destination
+    ///
+    /// The child is [inner](#method.is_inner) and inherits escaping and line prefix.
+    pub fn child(&mut self) -> RenderContext<'_> {
+        RenderContext {
+            writer: &mut *self.writer,
+            inner: true,
+            escape: self.escape,
+            line_prefix: self.line_prefix,
+            options: self.options,
+            line: Cow::Borrowed(&*self.line),
+            before_bracket: false,
+            document_start: false,
+            list_item: None,
+        }
+    }
+
+    /// Sets whether the element is inside another element
+    pub fn with_inner(mut self, inner: bool) -> Self {
+        self.inner = inner;
+        self
+    }
+
+    /// Sets the mode used for escaping strings
+    ///
+    /// Has no effect once escaping was disabled with
+    /// [Escaping::None](enum.Escaping.html#variant.None), so it stays
+    /// disabled for all descendants.
+    pub fn with_escape(mut self, escape: Escaping) -> Self {
+        if !matches!(self.escape, Escaping::None) {
+            self.escape = escape;
+        }
+        self
+    }
+
+    /// Sets the prefix written before each line
+    pub fn with_line_prefix(mut self, line_prefix: Option<&'a [u8]>) -> Self {
+        self.line_prefix = line_prefix;
+        self
+    }
+
+    /// Returns `true` if the element is inside another element, `false` otherwise
+    pub fn is_inner(&self) -> bool {
+        self.inner
+    }
+
+    /// Returns the mode used for escaping strings
+    pub fn escape(&self) -> Escaping {
+        self.escape
+    }
+
+    /// Returns the prefix written before each line
+    pub fn line_prefix(&self) -> Option<&'a [u8]> {
+        self.line_prefix
+    }
+
+    /// Returns the document's formatting choices
+    pub fn options(&self) -> &'a RenderOptions {
+        self.options
+    }
+
+    /// Shares the line state with other contexts writing the same line
+    fn with_line(mut self, line: &'a Cell<LineState>) -> Self {
+        self.line = Cow::Borrowed(line);
+        self
+    }
+
+    /// Sets whether the element is directly followed by one starting with `[`
+    fn with_before_bracket(mut self, before_bracket: bool) -> Self {
+        self.before_bracket = before_bracket;
+        self
+    }
+
+    fn before_bracket(&self) -> bool {
+        self.before_bracket
+    }
+
+    /// Marks the element as the content of a list item, given the prefix
+    /// up to the item's marker and the marker's width
+    fn with_list_item(mut self, marker_prefix: &'a [u8], marker_width: usize) -> Self {
+        self.list_item = Some((marker_prefix, marker_width));
+        self
+    }
+
+    /// Sets whether the element is the first block of the document
+    fn with_document_start(mut self, document_start: bool) -> Self {
+        self.document_start = document_start;
+        self
+    }
+
+    fn line_state(&self) -> LineState {
+        self.line.get()
+    }
+
+    /// Marks the output as being at the start of a container's line,
+    /// after a line prefix or list marker
+    fn start_line(&self) {
+        self.line.set(LineState::Start);
+    }
+
+    fn track(&self, buf: &[u8]) {
+        // Only the bytes after the last line break matter
+        let (mut line, rest) = match buf.iter().rposition(|c| *c == b'\n' || *c == b'\r') {
+            Some(i) => (LineState::Start, &buf[i + 1..]),
+            Option::None => (self.line.get(), buf),
+        };
+        for c in rest {
+            if line == LineState::Text {
+                break;
+            }
+            line = line.next(*c);
+        }
+        self.line.set(line);
+    }
+}
+
+impl Write for RenderContext<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.track(&buf[..written]);
+        Ok(written)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.writer.write_all(buf)?;
+        self.track(buf);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
 }
 
 /// Struct for generating Markdown
+///
+/// Every write is a block: consecutive writes are separated by exactly one
+/// blank line, see [RenderOptions::compact](struct.RenderOptions.html#method.compact)
+/// for exceptions. Writing a [BlankLine](struct.BlankLine.html) adds another.
 pub struct Markdown<W: Write> {
     writer: W,
+    options: RenderOptions,
+    trailing: Option<usize>,
+    closed: bool,
+    html: bool,
+    blank_lines: usize,
+    trim: bool,
+    line_ending: LineEnding,
+    bom: bool,
+}
+
+/// Line ending written by [Markdown](struct.Markdown.html), see
+/// [Markdown::line_ending](struct.Markdown.html#method.line_ending)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LineEnding {
+    /// `\n`, used on Unix-like systems
+    Lf,
+    /// `\r\n`, used on Windows
+    Crlf,
+}
+
+impl<F: fmt::Write> Markdown<FmtWriter<F>> {
+    /// Creates a new [Markdown](struct.Markdown.html) struct writing to a
+    /// `std::fmt::Write` destination, such as `&mut String`
+    ///
+    /// # Example
+    /// ```
+    /// use markdown_gen::markdown::{AsMarkdown, Markdown};
+    ///
+    /// let mut text = String::new();
+    /// let mut md = Markdown::new_fmt(&mut text);
+    /// md.write("Title".heading(1)).unwrap();
+    /// assert_eq!(text, "# Title\n");
+    /// ```
+    pub fn new_fmt(target: F) -> Self {
+        Self::new(FmtWriter::new(target))
+    }
 }
 
 impl<W: Write> Markdown<W> {
@@ -26,856 +395,4978 @@ impl<W: Write> Markdown<W> {
     ///
     /// * `writer` - Destination for Markdown data
     pub fn new(writer: W) -> Self {
-        Self { writer }
+        Self::with_options(writer, RenderOptions::new())
+    }
+
+    /// Creates a new [Markdown](struct.Markdown.html) struct formatting the
+    /// document according to `options`
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Destination for Markdown data
+    /// * `options` - Formatting choices, see [RenderOptions](struct.RenderOptions.html)
+    pub fn with_options(writer: W, options: RenderOptions) -> Self {
+        Self {
+            writer,
+            options,
+            trailing: None,
+            closed: false,
+            html: false,
+            blank_lines: 0,
+            trim: false,
+            line_ending: LineEnding::Lf,
+            bom: false,
+        }
+    }
+
+    /// Strips trailing spaces from every written line
+    ///
+    /// Two or more spaces ending a line with text are kept if another line
+    /// with text follows, as they are an intentional hard line break. Lines
+    /// containing only spaces and quote markers are always stripped.
+    pub fn trim_trailing_whitespace(mut self, trim: bool) -> Self {
+        self.trim = trim;
+        self
+    }
+
+    /// Sets the line ending written at the end of every line
+    ///
+    /// Defaults to [LineEnding::Lf](enum.LineEnding.html#variant.Lf). `\r\n`
+    /// in the text of elements is treated as a single line break, so it is
+    /// written with the chosen line ending and line prefixes such as quote
+    /// markers apply to it. A lone `\r` is written as a line break too.
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
     }
 
     /// Returns the underlying `writer` and consumes the object
+    ///
+    /// The writer isn't flushed, call [flush](#method.flush) first if it
+    /// buffers its output.
     pub fn into_inner(self) -> W {
         self.writer
     }
 
+    /// Flushes the underlying `writer`
+    ///
+    /// Buffered writers such as `BufWriter<File>` only flush on drop, where
+    /// errors are silently ignored. Flushing explicitly reports them.
+    ///
+    /// # Returns
+    /// `()` or `std::io::Error` if an error occurred during flushing the underlying writer
+    pub fn flush(&mut self) -> Result<(), io::Error> {
+        self.writer.flush()
+    }
+
+    /// Replaces the underlying `writer` and returns the previous one
+    ///
+    /// Useful for writing several documents, e.g. to rotating files,
+    /// through the same [Markdown](struct.Markdown.html) struct
+    pub fn set_writer(&mut self, writer: W) -> W {
+        self.trailing = None;
+        self.closed = false;
+        self.html = false;
+        self.blank_lines = 0;
+        self.bom = false;
+        std::mem::replace(&mut self.writer, writer)
+    }
+
+    /// Writes the UTF-8 byte order mark `EF BB BF` expected by some Windows tools
+    ///
+    /// The mark is written at most once per writer, further calls do nothing.
+    ///
+    /// # Returns
+    /// `()`, `std::io::Error` of kind `InvalidInput` if content was already
+    /// written or `std::io::Error` if an error occurred during writing to the
+    /// underlying writer
+    pub fn write_bom(&mut self) -> Result<(), io::Error> {
+        if self.bom {
+            return Ok(());
+        }
+        if self.trailing.is_some() {
+            return Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                "Byte order mark must be written before any content.",
+            ));
+        }
+        self.writer.write_all(b"\xEF\xBB\xBF")?;
+        self.bom = true;
+        Ok(())
+    }
+
     /// Writes a [MarkdownWritable](trait.MarkdownWritable.html) to the document
     ///
     /// # Returns
     /// `()` or `std::io::Error` if an error occurred during writing to the underlying writer
     pub fn write<T: MarkdownWritable>(&mut self, element: T) -> Result<(), io::Error> {
-        element.write_to(&mut self.writer, false, Normal, None)?;
+        if element.is_blank_line() {
+            if self.trailing.is_some() {
+                self.blank_lines += 1;
+            }
+            return Ok(());
+        }
+        let document_start = self.trailing.is_none();
+        let mut writer = self.block_writer();
+        let options = writer.options;
+        element.write_to(
+            &mut RenderContext::new(&mut writer, options).with_document_start(document_start),
+        )?;
+        let block = writer.finish();
+        self.end_block(block);
         Ok(())
     }
-}
 
-/// Trait for objects writable to Markdown documents
-pub trait MarkdownWritable {
-    /// Writes `self` as markdown to `writer`
+    /// Writes each of `elements` to the document in order
     ///
-    /// # Arguments
-    /// * `writer` - Destination writer
-    /// * `inner` - `true` if element is inside another element, `false` otherwise
-    /// * `escape` - Mode used for escaping string
-    /// * `line_prefix` - Prefix written before each line
+    /// Blocks are separated the same way as with consecutive calls to
+    /// [write](#method.write).
     ///
     /// # Returns
-    /// `()` or `std::io::Error` if an error occurred during writing
-    fn write_to(
-        &self,
-        writer: &mut dyn Write,
-        inner: bool,
-        escape: Escaping,
-        line_prefix: Option<&[u8]>,
-    ) -> Result<(), io::Error>;
+    /// `()` or `std::io::Error` if an error occurred during writing to the underlying writer
+    pub fn write_all<T: MarkdownWritable>(&mut self, elements: &[T]) -> Result<(), io::Error> {
+        for element in elements {
+            self.write(element)?;
+        }
+        Ok(())
+    }
 
-    /// Counts length of longest streak of `char` in `self`
+    /// Writes `text` verbatim to the underlying writer
+    ///
+    /// This bypasses everything [write](#method.write) takes care of: `text`
+    /// isn't escaped, separated from the previous block nor trimmed, so it can
+    /// produce broken Markdown or change the meaning of the surrounding
+    /// blocks. Only its trailing newlines are taken into account when
+    /// separating it from the next block.
+    pub fn write_raw(&mut self, text: &str) -> Result<(), io::Error> {
+        self.writer.write_all(text.as_bytes())?;
+        if !text.is_empty() {
+            let content = text.trim_end_matches('\n');
+            let newlines = text.len() - content.len();
+            self.trailing = match self.trailing {
+                Some(trailing) if content.is_empty() => Some(trailing + newlines),
+                _ => Some(newlines),
+            };
+            self.closed = false;
+            self.html = text.starts_with('<');
+            self.blank_lines = 0;
+        }
+        Ok(())
+    }
+
+    /// Writes a heading whose content is streamed by `content`
+    ///
+    /// Unlike [Heading](struct.Heading.html), nothing is buffered: the content
+    /// goes straight to the underlying writer.
     ///
     /// # Arguments
-    /// * `char` - Character to search for
-    /// * `carry` - Length to add to possible occurrence at the beginning
+    /// * `level` - Heading level (1-6)
+    /// * `content` - Closure writing the heading's text
+    pub fn heading<F>(&mut self, level: usize, content: F) -> Result<(), io::Error>
+    where
+        F: FnOnce(&mut InlineWriter) -> Result<(), io::Error>,
+    {
+        assert!(level > 0 && level <= 6, "Heading level must be range 1-6.");
+        let mut writer = self.block_writer();
+        writer.write_all(&b"###### "[6 - level..])?;
+        let options = writer.options;
+        content(&mut InlineWriter {
+            writer: &mut writer,
+            options,
+            line: Cell::new(LineState::Text),
+        })?;
+        writer.write_all(b"\n")?;
+        let block = writer.finish();
+        self.end_block(block);
+        Ok(())
+    }
+
+    /// Writes a paragraph whose content is streamed by `content`
     ///
-    /// # Returns
-    /// `(count, carry)`
-    /// * `count` - Length of longest streak
-    /// * `carry` - Length of streak at the end
-    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize);
+    /// Unlike [Paragraph](struct.Paragraph.html), nothing is buffered: the
+    /// content goes straight to the underlying writer.
+    pub fn paragraph<F>(&mut self, content: F) -> Result<(), io::Error>
+    where
+        F: FnOnce(&mut InlineWriter) -> Result<(), io::Error>,
+    {
+        let mut writer = self.block_writer();
+        let options = writer.options;
+        content(&mut InlineWriter {
+            writer: &mut writer,
+            options,
+            line: Cell::new(LineState::Start),
+        })?;
+        writer.write_all(b"\n\n")?;
+        let block = writer.finish();
+        self.end_block(block);
+        Ok(())
+    }
+
+    fn block_writer(&mut self) -> BlockWriter<'_> {
+        BlockWriter {
+            writer: &mut self.writer,
+            options: &self.options,
+            trailing: self.trailing,
+            closed: self.closed,
+            html: self.html,
+            heading: false,
+            line: Some((0, 0)),
+            blank_lines: self.blank_lines,
+            content: false,
+            held: 0,
+            trim: self.trim,
+            spaces: 0,
+            blank: true,
+            crlf: self.line_ending == LineEnding::Crlf,
+            cr: false,
+        }
+    }
+
+    fn end_block(&mut self, block: Option<(usize, bool, bool)>) {
+        if let Some((trailing, closed, html)) = block {
+            self.trailing = Some(trailing);
+            self.closed = closed;
+            self.html = html;
+            self.blank_lines = 0;
+        }
+    }
+}
+
+/// Writer separating a block written by [Markdown](struct.Markdown.html) from
+/// the previous one by exactly one blank line
+///
+/// Leading newlines of the block are replaced with as many as are needed on
+/// top of the ones the previous block ended with, and dropped if it is the
+/// first block.
+struct BlockWriter<'w> {
+    writer: &'w mut dyn Write,
+    options: &'w RenderOptions,
+    /// Newlines the previous block ended with, `None` if nothing was written
+    trailing: Option<usize>,
+    /// `true` if the previous block can't continue on the next line, later
+    /// whether the last line of the current one closes it
+    closed: bool,
+    /// `true` if the previous block is HTML, which only a blank line ends,
+    /// later the current one
+    html: bool,
+    /// `true` if the current block is an ATX heading
+    heading: bool,
+    /// Byte the current line repeats and how often, `(0, 0)` while the line
+    /// is empty and `None` once it mixes bytes
+    line: Option<(u8, usize)>,
+    /// Blank lines to add on top of the separator
+    blank_lines: usize,
+    content: bool,
+    /// Trailing newlines not written yet, see [RenderOptions::compact](struct.RenderOptions.html#method.compact)
+    held: usize,
+    /// `true` to strip trailing spaces, see [Markdown::trim_trailing_whitespace](struct.Markdown.html#method.trim_trailing_whitespace)
+    trim: bool,
+    spaces: usize,
+    blank: bool,
+    /// `true` to write `\r\n` instead of `\n`
+    crlf: bool,
+    /// `true` if the previous write ended with `\r`, which may start a `\r\n`
+    cr: bool,
+}
+
+impl BlockWriter<'_> {
+    /// Writes `data` to the underlying writer with the chosen line ending
+    fn emit(&mut self, data: &[u8]) -> io::Result<()> {
+        if !self.crlf {
+            return self.writer.write_all(data);
+        }
+        let mut lines = data.split(|x| *x == b'\n');
+        self.writer.write_all(lines.next().unwrap_or_default())?;
+        for line in lines {
+            self.writer.write_all(b"\r\n")?;
+            self.writer.write_all(line)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces `\r\n` and lone `\r` in `data` with `\n`
+    ///
+    /// A trailing `\r` is replaced right away, a `\n` starting the next write
+    /// completes it and is skipped.
+    fn normalize(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut data = data;
+        if std::mem::take(&mut self.cr) && data.first() == Some(&b'\n') {
+            data = &data[1..];
+        }
+        let mut normalized = Vec::with_capacity(data.len());
+        for (i, c) in data.iter().enumerate() {
+            if *c == b'\r' {
+                match data.get(i + 1) {
+                    Some(b'\n') => continue,
+                    None => self.cr = true,
+                    _ => {}
+                }
+                normalized.push(b'\n');
+            } else {
+                normalized.push(*c);
+            }
+        }
+        normalized
+    }
+
+    /// Writes `data`, holding back spaces until it is known whether they end a line
+    fn write_trimmed(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut lines = data.split(|x| *x == b'\n').peekable();
+        let mut line = lines.next().unwrap_or_default();
+        loop {
+            let text = line.len() - line.iter().rev().take_while(|x| **x == b' ').count();
+            if text > 0 {
+                for _ in 0..self.spaces {
+                    self.emit(b" ")?;
+                }
+                self.emit(&line[..text])?;
+                self.blank &= line[..text].iter().all(|x| *x == b'>' || *x == b' ');
+                self.spaces = 0;
+            }
+            self.spaces += line.len() - text;
+            match lines.next() {
+                Some(next) => {
+                    // A hard break is only kept if a line with text follows
+                    let blank_next =
+                        lines.peek().is_some() && next.iter().all(|x| *x == b'>' || *x == b' ');
+                    if !self.blank && !blank_next && self.spaces >= 2 {
+                        self.emit(b"  ")?;
+                    }
+                    self.emit(b"\n")?;
+                    self.spaces = 0;
+                    self.blank = true;
+                    line = next;
+                }
+                None => return Ok(()),
+            }
+        }
+    }
+
+    fn output(&mut self, data: &[u8]) -> io::Result<()> {
+        if self.trim {
+            self.write_trimmed(data)
+        } else {
+            self.emit(data)
+        }
+    }
+
+    /// Tracks whether the last line of the block closes it: a heading, a code
+    /// fence, a thematic break or a setext underline
+    fn track(&mut self, data: &[u8]) {
+        for c in data {
+            self.line = match self.line {
+                _ if *c == b'\n' => {
+                    self.end_line();
+                    Some((0, 0))
+                }
+                Some((0, 0)) => Some((*c, 1)),
+                Some((repeated, count)) if repeated == *c => Some((repeated, count + 1)),
+                _ => None,
+            };
+        }
+    }
+
+    fn end_line(&mut self) {
+        if self.line != Some((0, 0)) {
+            self.closed = self.heading
+                || matches!(self.line, Some((c, count)) if count >= 3 && b"`~=-*_".contains(&c));
+        }
+    }
+
+    /// Returns the number of newlines the block ended with, whether it is
+    /// closed and whether it is HTML, or `None` if it was empty
+    fn finish(mut self) -> Option<(usize, bool, bool)> {
+        self.end_line();
+        match self.trailing {
+            Some(trailing) if self.content => Some((trailing - self.held, self.closed, self.html)),
+            _ => None,
+        }
+    }
+}
+
+impl Write for BlockWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let normalized;
+        let mut data = buf;
+        if self.cr || data.contains(&b'\r') {
+            normalized = self.normalize(data);
+            data = &normalized;
+            if data.is_empty() {
+                return Ok(buf.len());
+            }
+        }
+        if !self.content {
+            data = &data[data.iter().take_while(|x| **x == b'\n').count()..];
+            if data.is_empty() {
+                return Ok(buf.len());
+            }
+            if let Some(trailing) = self.trailing {
+                // Headings and code fences end any block but HTML
+                let interrupts = data[0] == b'#' || data.starts_with(b"```");
+                let separator = if self.options.compact && !self.html && (self.closed || interrupts)
+                {
+                    1
+                } else {
+                    2
+                } + self.blank_lines;
+                for _ in trailing.min(separator)..separator {
+                    self.emit(b"\n")?;
+                }
+            }
+            // Text starting with `#` is escaped, so only headings start with it
+            self.heading = data.first() == Some(&b'#');
+            self.html = data.first() == Some(&b'<');
+            self.closed = self.heading;
+            self.content = true;
+        }
+        self.track(data);
+        let newlines = data.iter().rev().take_while(|x| **x == b'\n').count();
+        let body = &data[..data.len() - newlines];
+        let (written, run) = if body.is_empty() {
+            let run = self.trailing.unwrap_or(0);
+            (run - self.held, run + newlines)
+        } else {
+            let held = std::mem::take(&mut self.held);
+            self.output(&vec![b'\n'; held])?;
+            (0, newlines)
+        };
+        // Compact output holds back blank lines, the next block decides on them
+        let allowed = if self.options.compact {
+            run.min(1)
+        } else {
+            run
+        };
+        self.output(&data[..body.len() + allowed.saturating_sub(written)])?;
+        self.held = run - allowed.max(written);
+        self.trailing = Some(run);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes inline content of a streamed block, see [Markdown::paragraph](struct.Markdown.html#method.paragraph)
+pub struct InlineWriter<'w> {
+    writer: &'w mut dyn Write,
+    options: &'w RenderOptions,
+    line: Cell<LineState>,
+}
+
+impl InlineWriter<'_> {
+    /// Writes escaped text
+    pub fn text(&mut self, text: &str) -> Result<(), io::Error> {
+        text.write_to(&mut self.context())
+    }
+
+    /// Writes an inline [MarkdownWritable](trait.MarkdownWritable.html)
+    pub fn write<T: MarkdownWritable>(&mut self, element: T) -> Result<(), io::Error> {
+        element.write_to(&mut self.context())
+    }
+
+    fn context(&mut self) -> RenderContext<'_> {
+        RenderContext::new(self.writer, self.options)
+            .with_inner(true)
+            .with_line(&self.line)
+    }
+}
+
+/// Renders a [MarkdownWritable](trait.MarkdownWritable.html) to a `String`
+///
+/// # Panics
+/// If the rendered document is not valid UTF-8
+pub fn to_markdown_string<T: MarkdownWritable>(element: T) -> String {
+    let mut md = Markdown::new(Vec::new());
+    md.write(element)
+        .expect("Writing to a Vec<u8> cannot fail.");
+    String::from_utf8(md.into_inner()).expect("Rendered Markdown is not valid UTF-8.")
+}
+
+/// Returns how many backticks delimit a code span containing `text`
+///
+/// One more than the longest run of backticks in `text`, so the delimiter
+/// can't be closed early. Code blocks need a fence of at least 3.
+pub fn backticks_needed(text: &str) -> usize {
+    let (count, carry) = text.count_max_streak(b'`', 0);
+    count.max(carry) + 1
+}
+
+/// Writer discarding its input and counting how many bytes were written
+///
+/// Measures rendered output without keeping it in memory.
+///
+/// # Example
+/// ```
+/// use markdown_gen::markdown::{AsMarkdown, CountingWriter, Markdown};
+///
+/// let mut md = Markdown::new(CountingWriter::new());
+/// md.write("Title".heading(1)).unwrap();
+/// assert_eq!(md.into_inner().count(), "# Title\n".len());
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct CountingWriter {
+    count: usize,
+}
+
+impl CountingWriter {
+    /// Creates a writer which counted no bytes yet
+    pub fn new() -> Self {
+        Self { count: 0 }
+    }
+
+    /// Returns the number of bytes written so far
+    pub fn count(&self) -> usize {
+        self.count
+    }
 }
 
-/// Trait for objects convertible to a Markdown element
-pub trait AsMarkdown<'a> {
-    /// Converts `self` to [Paragraph](struct.Paragraph.html)
-    fn paragraph(self) -> Paragraph<'a>;
-    /// Converts `self` to [Heading](struct.Heading.html)
-    ///
-    /// # Arguments
-    /// * `level` - Heading level (1-6)
-    fn heading(self, level: usize) -> Heading<'a>;
-    /// Converts `self` to [Link](struct.Link.html)
-    ///
-    /// # Arguments
-    /// * `address` - Address which will the link lead to
-    fn link_to(self, address: &'a str) -> Link<'a>;
+impl Default for CountingWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.count += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Trait for objects writable to Markdown documents
+pub trait MarkdownWritable {
+    /// Writes `self` as markdown to `ctx`
+    ///
+    /// # Arguments
+    /// * `ctx` - Destination writer along with escaping, line prefix and
+    ///   formatting options, see [RenderContext](struct.RenderContext.html)
+    ///
+    /// # Returns
+    /// `()` or `std::io::Error` if an error occurred during writing
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), io::Error>;
+
+    /// Counts length of longest streak of `char` in `self`
+    ///
+    /// The search is byte-oriented, so `char` must be an ASCII character.
+    /// ASCII bytes never occur inside multi-byte UTF-8 sequences, so the count
+    /// is exact regardless of other characters surrounding the streak.
+    ///
+    /// # Arguments
+    /// * `char` - ASCII character to search for
+    /// * `carry` - Length to add to possible occurrence at the beginning
+    ///
+    /// # Returns
+    /// `(count, carry)`
+    /// * `count` - Length of longest streak
+    /// * `carry` - Length of streak at the end
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize);
+
+    /// Returns `true` if `self` is a block element
+    ///
+    /// Containers holding both inline and block children separate
+    /// consecutive blocks with a blank line.
+    fn is_block(&self) -> bool {
+        false
+    }
+
+    /// Returns `true` if `self` is a [BlankLine](struct.BlankLine.html)
+    ///
+    /// Containers separating blocks add a blank line to the separator
+    /// instead of writing it.
+    #[doc(hidden)]
+    fn is_blank_line(&self) -> bool {
+        false
+    }
+
+    /// Returns `true` if `self` is a container writing nothing, like an empty
+    /// [Paragraph](struct.Paragraph.html)
+    ///
+    /// Containers separating blocks skip it instead of separating it.
+    #[doc(hidden)]
+    fn is_empty_block(&self) -> bool {
+        false
+    }
+
+    /// Returns `true` if `self` is written starting with `[`
+    ///
+    /// Containers tell the preceding element, so a `!` it ends with is
+    /// escaped instead of turning both into an image.
+    #[doc(hidden)]
+    fn starts_with_bracket(&self) -> bool {
+        false
+    }
+
+    /// Returns `self` if it is a [Heading](struct.Heading.html)
+    ///
+    /// Lets [Document](struct.Document.html) inspect the heading hierarchy.
+    #[doc(hidden)]
+    fn as_heading(&self) -> Option<&Heading<'_>> {
+        None
+    }
+
+    /// Calls the [Visitor](trait.Visitor.html) method matching `self`
+    ///
+    /// Containers then pass `visitor` on to their children in order, so the
+    /// whole tree is traversed without being rendered. Does nothing by default.
+    fn accept(&self, _visitor: &mut dyn Visitor) {}
+
+    /// Converts `self` to a child stored by container elements
+    ///
+    /// Strings are stored inline, everything else is boxed
+    #[doc(hidden)]
+    fn into_child<'a>(self) -> Child<'a>
+    where
+        Self: Sized + 'a,
+    {
+        Child::Boxed(Box::new(self))
+    }
+
+    /// Converts a reference to `self` to a child stored by container elements
+    ///
+    /// Lets references share the rendering of the owned element without
+    /// being boxed.
+    #[doc(hidden)]
+    fn borrowed_child(&self) -> Child<'_>
+    where
+        Self: Sized,
+    {
+        Child::Borrowed(self)
+    }
+
+    /// Wraps `self` in a type implementing `std::fmt::Display`
+    ///
+    /// # Example
+    /// ```
+    /// use markdown_gen::markdown::{AsMarkdown, MarkdownWritable};
+    ///
+    /// let heading = "Title".heading(1);
+    /// assert_eq!(format!("{}", heading.display()), "# Title\n");
+    /// ```
+    fn display(&self) -> MarkdownDisplay<'_, Self>
+    where
+        Self: Sized,
+    {
+        MarkdownDisplay { element: self }
+    }
+}
+
+/// Renders a [MarkdownWritable](trait.MarkdownWritable.html) through `std::fmt::Display`
+pub struct MarkdownDisplay<'a, T: MarkdownWritable> {
+    element: &'a T,
+}
+
+impl<T: MarkdownWritable> fmt::Display for MarkdownDisplay<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.element
+            .write_to(&mut RenderContext::new(
+                &mut FmtWriter(f),
+                &RenderOptions::new(),
+            ))
+            .map_err(|_| fmt::Error)
+    }
+}
+
+/// Adapter writing `std::io::Write` output to a `std::fmt::Write` destination
+///
+/// Writes which aren't valid UTF-8 fail with an error of kind `InvalidData`.
+/// See [Markdown::new_fmt](struct.Markdown.html#method.new_fmt).
+pub struct FmtWriter<W: fmt::Write>(W);
+
+impl<W: fmt::Write> FmtWriter<W> {
+    /// Creates an adapter writing to `target`
+    pub fn new(target: W) -> Self {
+        Self(target)
+    }
+
+    /// Returns the underlying `target` and consumes the object
+    pub fn into_inner(self) -> W {
+        self.0
+    }
+}
+
+impl<W: fmt::Write> Write for FmtWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let text =
+            std::str::from_utf8(buf).map_err(|e| Error::new(io::ErrorKind::InvalidData, e))?;
+        self.0.write_str(text).map_err(Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Element stored inside a container
+#[doc(hidden)]
+pub enum Child<'a> {
+    Text(&'a str),
+    Borrowed(&'a dyn MarkdownWritable),
+    Boxed(Box<dyn 'a + MarkdownWritable>),
+}
+
+impl MarkdownWritable for Child<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        match self {
+            Child::Text(text) => text.write_to(ctx),
+            Child::Borrowed(element) => element.write_to(ctx),
+            Child::Boxed(element) => element.write_to(ctx),
+        }
+    }
+
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+        match self {
+            Child::Text(text) => text.count_max_streak(char, carry),
+            Child::Borrowed(element) => element.count_max_streak(char, carry),
+            Child::Boxed(element) => element.count_max_streak(char, carry),
+        }
+    }
+
+    fn is_block(&self) -> bool {
+        match self {
+            Child::Text(_) => false,
+            Child::Borrowed(element) => element.is_block(),
+            Child::Boxed(element) => element.is_block(),
+        }
+    }
+
+    fn is_blank_line(&self) -> bool {
+        match self {
+            Child::Text(_) => false,
+            Child::Borrowed(element) => element.is_blank_line(),
+            Child::Boxed(element) => element.is_blank_line(),
+        }
+    }
+
+    fn is_empty_block(&self) -> bool {
+        match self {
+            Child::Text(_) => false,
+            Child::Borrowed(element) => element.is_empty_block(),
+            Child::Boxed(element) => element.is_empty_block(),
+        }
+    }
+
+    fn starts_with_bracket(&self) -> bool {
+        match self {
+            Child::Text(text) => text.starts_with_bracket(),
+            Child::Borrowed(element) => element.starts_with_bracket(),
+            Child::Boxed(element) => element.starts_with_bracket(),
+        }
+    }
+
+    fn as_heading(&self) -> Option<&Heading<'_>> {
+        match self {
+            Child::Text(_) => None,
+            Child::Borrowed(element) => element.as_heading(),
+            Child::Boxed(element) => element.as_heading(),
+        }
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        match self {
+            Child::Text(text) => text.accept(visitor),
+            Child::Borrowed(element) => element.accept(visitor),
+            Child::Boxed(element) => element.accept(visitor),
+        }
+    }
+}
+
+impl<'b> MarkdownWritable for Box<dyn 'b + MarkdownWritable> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        self.as_ref().write_to(ctx)
+    }
+
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+        self.as_ref().count_max_streak(char, carry)
+    }
+
+    fn is_block(&self) -> bool {
+        self.as_ref().is_block()
+    }
+
+    fn is_blank_line(&self) -> bool {
+        self.as_ref().is_blank_line()
+    }
+
+    fn is_empty_block(&self) -> bool {
+        self.as_ref().is_empty_block()
+    }
+
+    fn starts_with_bracket(&self) -> bool {
+        self.as_ref().starts_with_bracket()
+    }
+
+    fn as_heading(&self) -> Option<&Heading<'_>> {
+        self.as_ref().as_heading()
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        self.as_ref().accept(visitor)
+    }
+
+    fn into_child<'a>(self) -> Child<'a>
+    where
+        Self: 'a,
+    {
+        Child::Boxed(self)
+    }
+}
+
+impl<T: MarkdownWritable> MarkdownWritable for &T {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        (*self).write_to(ctx)
+    }
+
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+        (*self).count_max_streak(char, carry)
+    }
+
+    fn is_block(&self) -> bool {
+        (*self).is_block()
+    }
+
+    fn is_blank_line(&self) -> bool {
+        (*self).is_blank_line()
+    }
+
+    fn is_empty_block(&self) -> bool {
+        (*self).is_empty_block()
+    }
+
+    fn starts_with_bracket(&self) -> bool {
+        (*self).starts_with_bracket()
+    }
+
+    fn as_heading(&self) -> Option<&Heading<'_>> {
+        (*self).as_heading()
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        (*self).accept(visitor)
+    }
+
+    fn into_child<'a>(self) -> Child<'a>
+    where
+        Self: 'a,
+    {
+        self.borrowed_child()
+    }
+}
+
+/// Traverses the element tree without rendering it
+///
+/// Passed to [MarkdownWritable::accept](trait.MarkdownWritable.html#method.accept),
+/// which calls the method matching each element before visiting its children.
+/// All methods do nothing by default, so only the interesting ones need to
+/// be implemented.
+///
+/// # Example
+/// ```
+/// use markdown_gen::markdown::{AsMarkdown, Document, Heading, MarkdownWritable, Visitor};
+///
+/// struct Levels(Vec<usize>);
+///
+/// impl Visitor for Levels {
+///     fn visit_heading(&mut self, heading: &Heading) {
+///         self.0.push(heading.level());
+///     }
+/// }
+///
+/// let document = Document::new()
+///     .append("Title".heading(1))
+///     .append("Section".heading(2));
+/// let mut levels = Levels(Vec::new());
+/// document.accept(&mut levels);
+/// assert_eq!(levels.0, vec![1, 2]);
+/// ```
+#[allow(unused_variables)]
+pub trait Visitor {
+    /// Visits plain text, including the text of [RichText](struct.RichText.html)
+    /// and table headers
+    fn visit_text(&mut self, text: &str) {}
+    /// Visits a [FrontMatter](struct.FrontMatter.html)
+    fn visit_front_matter(&mut self, front_matter: &FrontMatter) {}
+    /// Visits a [Document](struct.Document.html)
+    fn visit_document(&mut self, document: &Document) {}
+    /// Visits a [Paragraph](struct.Paragraph.html)
+    fn visit_paragraph(&mut self, paragraph: &Paragraph) {}
+    /// Visits a [Span](struct.Span.html)
+    fn visit_span(&mut self, span: &Span) {}
+    /// Visits a [Heading](struct.Heading.html)
+    fn visit_heading(&mut self, heading: &Heading) {}
+    /// Visits a [TableOfContents](struct.TableOfContents.html)
+    fn visit_table_of_contents(&mut self, toc: &TableOfContents) {}
+    /// Visits a [Table](struct.Table.html)
+    fn visit_table(&mut self, table: &Table) {}
+    /// Visits a [Link](struct.Link.html)
+    fn visit_link(&mut self, link: &Link) {}
+    /// Visits an [Autolink](struct.Autolink.html)
+    fn visit_autolink(&mut self, autolink: &Autolink) {}
+    /// Visits a [ReferenceTable](struct.ReferenceTable.html)
+    fn visit_reference_table(&mut self, table: &ReferenceTable) {}
+    /// Visits a [ReferenceLink](struct.ReferenceLink.html)
+    fn visit_reference_link(&mut self, link: &ReferenceLink) {}
+    /// Visits an [Image](struct.Image.html)
+    fn visit_image(&mut self, image: &Image) {}
+    /// Visits a [RichText](struct.RichText.html)
+    fn visit_rich_text(&mut self, text: &RichText) {}
+    /// Visits a [List](struct.List.html)
+    fn visit_list(&mut self, list: &List) {}
+    /// Visits a [Quote](struct.Quote.html)
+    fn visit_quote(&mut self, quote: &Quote) {}
+    /// Visits a [CodeBlock](struct.CodeBlock.html)
+    fn visit_code_block(&mut self, code_block: &CodeBlock) {}
+    /// Visits an [HtmlBlock](struct.HtmlBlock.html)
+    fn visit_html_block(&mut self, html_block: &HtmlBlock) {}
+    /// Visits a [Comment](struct.Comment.html)
+    fn visit_comment(&mut self, comment: &Comment) {}
+    /// Visits an [InlineHtml](struct.InlineHtml.html)
+    fn visit_inline_html(&mut self, html: &InlineHtml) {}
+    /// Visits a [Strikethrough](struct.Strikethrough.html)
+    #[cfg(feature = "gfm")]
+    fn visit_strikethrough(&mut self, strikethrough: &Strikethrough) {}
+    /// Visits a [Subscript](struct.Subscript.html)
+    fn visit_subscript(&mut self, subscript: &Subscript) {}
+    /// Visits a [Superscript](struct.Superscript.html)
+    fn visit_superscript(&mut self, superscript: &Superscript) {}
+    /// Visits a [Kbd](struct.Kbd.html)
+    fn visit_kbd(&mut self, kbd: &Kbd) {}
+    /// Visits an [Anchor](struct.Anchor.html)
+    fn visit_anchor(&mut self, anchor: &Anchor) {}
+    /// Visits a [Raw](struct.Raw.html)
+    fn visit_raw(&mut self, raw: &Raw) {}
+    /// Visits a [Prefixed](struct.Prefixed.html) wrapper, before its element
+    fn visit_prefixed(&mut self, prefixed: &Prefixed) {}
+    /// Visits a [Trusted](struct.Trusted.html) wrapper, before its element
+    fn visit_trusted(&mut self, trusted: &Trusted) {}
+    /// Visits a [Math](struct.Math.html)
+    fn visit_math(&mut self, math: &Math) {}
+    /// Visits a [MathBlock](struct.MathBlock.html)
+    fn visit_math_block(&mut self, math_block: &MathBlock) {}
+    /// Visits a [ThematicBreak](struct.ThematicBreak.html)
+    fn visit_thematic_break(&mut self, thematic_break: &ThematicBreak) {}
+    /// Visits a [LineBreak](struct.LineBreak.html)
+    fn visit_line_break(&mut self, line_break: &LineBreak) {}
+    /// Visits a [SoftBreak](struct.SoftBreak.html)
+    fn visit_soft_break(&mut self, soft_break: &SoftBreak) {}
+    /// Visits a [BlankLine](struct.BlankLine.html)
+    fn visit_blank_line(&mut self, blank_line: &BlankLine) {}
+    /// Visits a [Details](struct.Details.html)
+    fn visit_details(&mut self, details: &Details) {}
+    /// Visits a [Footnote](struct.Footnote.html)
+    #[cfg(feature = "gfm")]
+    fn visit_footnote(&mut self, footnote: &Footnote) {}
+    /// Visits a [FootnoteReference](struct.FootnoteReference.html)
+    #[cfg(feature = "gfm")]
+    fn visit_footnote_reference(&mut self, reference: &FootnoteReference) {}
+}
+
+/// Trait for objects convertible to a Markdown element
+pub trait AsMarkdown<'a> {
+    /// Converts `self` to [Paragraph](struct.Paragraph.html)
+    fn paragraph(self) -> Paragraph<'a>;
+    /// Converts `self` to [Heading](struct.Heading.html)
+    ///
+    /// # Arguments
+    /// * `level` - Heading level (1-6)
+    fn heading(self, level: usize) -> Heading<'a>;
+    /// Converts `self` to [Link](struct.Link.html)
+    ///
+    /// # Arguments
+    /// * `address` - Address which will the link lead to
+    fn link_to(self, address: &'a str) -> Link<'a>;
+
+    /// Converts `self` to **bold** [RichText](struct.RichText.html)
+    fn bold(self) -> RichText<'a>;
+
+    /// Converts `self` to *italic* [RichText](struct.RichText.html)
+    fn italic(self) -> RichText<'a>;
+
+    /// Converts `self` to `code` [RichText](struct.RichText.html)
+    ///
+    /// Writing inline code containing a line break fails with an error of kind
+    /// `InvalidInput`, use [CodeBlock](struct.CodeBlock.html) for multi-line code.
+    fn code(self) -> RichText<'a>;
+
+    /// Converts `self` to ==highlighted== [RichText](struct.RichText.html)
+    ///
+    /// Rendered by Pandoc's `mark` extension and by Obsidian. Implemented
+    /// through [try_highlight](#method.try_highlight), which types supporting
+    /// highlighting override.
+    ///
+    /// # Panics
+    /// If `self` can't be highlighted
+    #[cfg(feature = "pandoc")]
+    fn highlight(self) -> RichText<'a>
+    where
+        Self: Sized,
+    {
+        self.try_highlight().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Converts `self` to [Quote](struct.Quote.html)
+    fn quote(self) -> Quote<'a>;
+
+    /// Converts `self` to [Paragraph](struct.Paragraph.html), failing instead of panicking
+    fn try_paragraph(self) -> Result<Paragraph<'a>, ValidationError>
+    where
+        Self: Sized,
+    {
+        Ok(self.paragraph())
+    }
+
+    /// Converts `self` to [Heading](struct.Heading.html), failing instead of panicking
+    ///
+    /// # Arguments
+    /// * `level` - Heading level (1-6)
+    fn try_heading(self, level: usize) -> Result<Heading<'a>, ValidationError>
+    where
+        Self: Sized,
+    {
+        Heading::try_new(level)?;
+        Ok(self.heading(level))
+    }
+
+    /// Converts `self` to [Link](struct.Link.html), failing instead of panicking
+    ///
+    /// # Arguments
+    /// * `address` - Address which will the link lead to
+    fn try_link_to(self, address: &'a str) -> Result<Link<'a>, ValidationError>
+    where
+        Self: Sized,
+    {
+        Ok(self.link_to(address))
+    }
+
+    /// Converts `self` to **bold** [RichText](struct.RichText.html), failing instead of panicking
+    fn try_bold(self) -> Result<RichText<'a>, ValidationError>
+    where
+        Self: Sized,
+    {
+        Ok(self.bold())
+    }
+
+    /// Converts `self` to *italic* [RichText](struct.RichText.html), failing instead of panicking
+    fn try_italic(self) -> Result<RichText<'a>, ValidationError>
+    where
+        Self: Sized,
+    {
+        Ok(self.italic())
+    }
+
+    /// Converts `self` to `code` [RichText](struct.RichText.html), failing instead of panicking
+    fn try_code(self) -> Result<RichText<'a>, ValidationError>
+    where
+        Self: Sized,
+    {
+        Ok(self.code())
+    }
+
+    /// Converts `self` to ==highlighted== [RichText](struct.RichText.html), failing instead of panicking
+    #[cfg(feature = "pandoc")]
+    fn try_highlight(self) -> Result<RichText<'a>, ValidationError>
+    where
+        Self: Sized,
+    {
+        Err(ValidationError::Conversion(
+            "Cannot make this element highlighted",
+        ))
+    }
+
+    /// Converts `self` to [Quote](struct.Quote.html), failing instead of panicking
+    fn try_quote(self) -> Result<Quote<'a>, ValidationError>
+    where
+        Self: Sized,
+    {
+        Ok(self.quote())
+    }
+}
+
+/// Error returned for elements whose structure can't be written as Markdown
+///
+/// Returned by fallible constructors, [AsMarkdown](trait.AsMarkdown.html)
+/// conversions and validation methods. Writing an invalid element fails with
+/// a `std::io::Error` wrapping it, of kind `InvalidData` for the variants
+/// validation methods report and `InvalidInput` otherwise.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationError {
+    /// [AsMarkdown](trait.AsMarkdown.html) can't convert the element, e.g. a
+    /// [List](struct.List.html) to a heading
+    Conversion(&'static str),
+    /// Heading level outside of 1-6
+    HeadingLevel(usize),
+    /// Headings increasing their level by more than one, as
+    /// `(text, level, previous level)`
+    SkippedHeadingLevels(Vec<(String, usize, usize)>),
+    /// Table row whose number of cells doesn't match the header
+    TableRow {
+        /// Index of the row
+        row: usize,
+        /// Number of cells in the row
+        cells: usize,
+        /// Number of columns in the header
+        columns: usize,
+    },
+    /// Pipe table without columns
+    TableWithoutColumns,
+    /// [FrontMatter](struct.FrontMatter.html) which isn't the first block
+    FrontMatterNotFirst,
+    /// Inline code containing a line break
+    LineBreakInCode,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::Conversion(message) => f.write_str(message),
+            ValidationError::HeadingLevel(level) => {
+                write!(f, "Heading level must be range 1-6, got {}.", level)
+            }
+            ValidationError::SkippedHeadingLevels(headings) => {
+                f.write_str("Heading levels must not increase by more than one: ")?;
+                for (i, (text, level, previous)) in headings.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "\"{}\" (level {} after {})", text, level, previous)?;
+                }
+                f.write_str(".")
+            }
+            ValidationError::TableRow {
+                row,
+                cells,
+                columns,
+            } => write!(
+                f,
+                "Table row {} has {} cells, expected {}.",
+                row, cells, columns
+            ),
+            ValidationError::TableWithoutColumns => {
+                f.write_str("Table must have at least one column.")
+            }
+            ValidationError::FrontMatterNotFirst => {
+                f.write_str("Front matter must be the first block of the document.")
+            }
+            ValidationError::LineBreakInCode => {
+                f.write_str("Inline code cannot contain line breaks, use CodeBlock instead.")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl From<ValidationError> for Error {
+    fn from(error: ValidationError) -> Self {
+        let kind = match error {
+            ValidationError::SkippedHeadingLevels(_) | ValidationError::TableRow { .. } => {
+                io::ErrorKind::InvalidData
+            }
+            _ => io::ErrorKind::InvalidInput,
+        };
+        Error::new(kind, error)
+    }
+}
+
+//region FrontMatter
+/// YAML front matter placed at the very top of the document
+///
+/// Writing it anywhere but as the first block of a [Markdown](struct.Markdown.html)
+/// or [Document](struct.Document.html) fails with an error of kind `InvalidInput`.
+pub struct FrontMatter<'a> {
+    fields: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> FrontMatter<'a> {
+    /// Creates an empty front matter block
+    pub fn new() -> Self {
+        Self { fields: Vec::new() }
+    }
+
+    /// Adds a `key: value` line, written verbatim
+    pub fn field(mut self, key: &'a str, value: &'a str) -> Self {
+        self.fields.push((key, value));
+        self
+    }
+}
+
+impl Default for FrontMatter<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarkdownWritable for FrontMatter<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        if !ctx.document_start {
+            return Err(ValidationError::FrontMatterNotFirst.into());
+        }
+        ctx.write_all(b"---\n")?;
+        for (key, value) in &self.fields {
+            ctx.write_all(key.as_bytes())?;
+            ctx.write_all(b": ")?;
+            ctx.write_all(value.as_bytes())?;
+            ctx.write_all(b"\n")?;
+        }
+        ctx.write_all(b"---\n")?;
+        Ok(())
+    }
+
+    fn count_max_streak(&self, _: u8, _carry: usize) -> (usize, usize) {
+        (0, 0)
+    }
+
+    fn is_block(&self) -> bool {
+        true
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_front_matter(self);
+    }
+}
+//endregion
+
+//region Document
+/// Sequence of blocks separated by exactly one blank line
+pub struct Document<'a> {
+    children: Vec<Child<'a>>,
+    #[cfg(feature = "gfm")]
+    footnotes: Vec<Footnote<'a>>,
+}
+
+impl<'a> Document<'a> {
+    /// Creates an empty document
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Creates an empty document with room for `capacity` blocks
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            children: Vec::with_capacity(capacity),
+            #[cfg(feature = "gfm")]
+            footnotes: Vec::new(),
+        }
+    }
+
+    /// Returns the number of blocks in the document
+    ///
+    /// Footnote definitions aren't counted.
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Returns `true` if the document has no blocks
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Appends a block to the document
+    pub fn append<T: 'a + MarkdownWritable>(mut self, element: T) -> Self {
+        self.children.push(element.into_child());
+        self
+    }
+
+    /// Appends all blocks yielded by `elements` to the document
+    pub fn append_all<T, I>(mut self, elements: I) -> Self
+    where
+        T: 'a + MarkdownWritable,
+        I: IntoIterator<Item = T>,
+    {
+        self.children
+            .extend(elements.into_iter().map(MarkdownWritable::into_child));
+        self
+    }
+
+    /// Appends a heading containing `text` to the document
+    ///
+    /// # Arguments
+    /// * `level` - Heading level (1-6)
+    pub fn heading(self, level: usize, text: &'a str) -> Self {
+        self.append(text.heading(level))
+    }
+
+    /// Creates a table of contents linking to the headings of the document
+    ///
+    /// Only headings appended directly to the document are listed, see
+    /// [TableOfContents::from_headings](struct.TableOfContents.html#method.from_headings).
+    pub fn toc(&self) -> TableOfContents {
+        TableOfContents::collect(self.children.iter().filter_map(|child| child.as_heading()))
+    }
+
+    /// Adds a footnote definition written at the end of the document
+    ///
+    /// # Panics
+    /// If a footnote with the same id was already added
+    #[cfg(feature = "gfm")]
+    pub fn footnote(mut self, footnote: Footnote<'a>) -> Self {
+        assert!(
+            self.footnotes.iter().all(|f| f.id != footnote.id),
+            "Footnote ids must be unique."
+        );
+        self.footnotes.push(footnote);
+        self
+    }
+
+    /// Counts the headings, words and links in the document
+    ///
+    /// Words are whitespace separated runs of text, including the text of
+    /// headings, links, lists, tables and footnotes. Code blocks, raw HTML
+    /// and link addresses aren't counted.
+    pub fn stats(&self) -> DocumentStats {
+        let mut stats = DocumentStats::new();
+        self.accept(&mut stats);
+        stats
+    }
+
+    /// Checks that heading levels never increase by more than one
+    ///
+    /// Skipping levels, like a level 3 heading directly after a level 1 one,
+    /// is flagged by accessibility linters (MD001). Only headings appended
+    /// directly to the document are checked.
+    ///
+    /// # Returns
+    /// `()` or [ValidationError::SkippedHeadingLevels](enum.ValidationError.html#variant.SkippedHeadingLevels)
+    /// listing every offending heading
+    pub fn validate_headings(&self) -> Result<(), ValidationError> {
+        let mut previous: Option<usize> = None;
+        let mut offending = Vec::new();
+        for heading in self.children.iter().filter_map(|child| child.as_heading()) {
+            match previous {
+                Some(previous) if heading.level > previous + 1 => {
+                    offending.push((heading.text(), heading.level, previous))
+                }
+                _ => {}
+            }
+            previous = Some(heading.level);
+        }
+        if offending.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError::SkippedHeadingLevels(offending))
+        }
+    }
+}
+
+impl Default for Document<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Statistics returned by [Document::stats](struct.Document.html#method.stats)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DocumentStats {
+    /// Number of headings
+    pub headings: usize,
+    /// Number of words
+    pub words: usize,
+    /// Number of links, including autolinks and reference links
+    pub links: usize,
+}
+
+impl DocumentStats {
+    /// Creates statistics with every count at zero
+    pub fn new() -> Self {
+        Self {
+            headings: 0,
+            words: 0,
+            links: 0,
+        }
+    }
+}
+
+impl Default for DocumentStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Tallies the statistics of any element it visits, not just documents
+impl Visitor for DocumentStats {
+    fn visit_text(&mut self, text: &str) {
+        self.words += text.split_whitespace().count();
+    }
+
+    fn visit_heading(&mut self, _heading: &Heading) {
+        self.headings += 1;
+    }
+
+    fn visit_link(&mut self, _link: &Link) {
+        self.links += 1;
+    }
+
+    fn visit_autolink(&mut self, _autolink: &Autolink) {
+        self.links += 1;
+    }
+
+    fn visit_reference_link(&mut self, _link: &ReferenceLink) {
+        self.links += 1;
+    }
+}
+
+impl MarkdownWritable for Document<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let escape = ctx.escape();
+        let line_prefix = ctx.line_prefix();
+        let options = ctx.options();
+        let mut first = true;
+        let mut blank_lines = 0;
+        let mut block = Vec::new();
+        let blocks = self
+            .children
+            .iter()
+            .map(|child| child as &dyn MarkdownWritable);
+        #[cfg(feature = "gfm")]
+        let blocks = blocks.chain(self.footnotes.iter().map(|f| f as &dyn MarkdownWritable));
+        for child in blocks {
+            if child.is_blank_line() {
+                blank_lines += 1;
+                continue;
+            }
+            // Blocks disagree on the newlines around them, so each one is
+            // rendered on its own and trimmed before being separated
+            block.clear();
+            child.write_to(
+                &mut RenderContext::new(&mut block, options)
+                    .with_escape(escape)
+                    .with_document_start(first && ctx.document_start),
+            )?;
+            let start = block.iter().position(|x| *x != b'\n');
+            let end = block.iter().rposition(|x| *x != b'\n');
+            if let (Some(start), Some(end)) = (start, end) {
+                if !first {
+                    write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+                    for _ in 0..blank_lines {
+                        write_line_prefixed(ctx, b"\n", line_prefix)?;
+                    }
+                }
+                write_line_prefixed(ctx, &block[start..=end], line_prefix)?;
+                first = false;
+                blank_lines = 0;
+            }
+        }
+        if !inner && !first {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+        }
+        Ok(())
+    }
+
+    fn count_max_streak(&self, char: u8, _carry: usize) -> (usize, usize) {
+        let mut count = 0;
+        for child in &self.children {
+            let (c, cr) = child.count_max_streak(char, 0);
+            count = count.max(c).max(cr);
+        }
+        #[cfg(feature = "gfm")]
+        for footnote in &self.footnotes {
+            let (c, _) = footnote.count_max_streak(char, 0);
+            count = count.max(c);
+        }
+        (count, 0)
+    }
+
+    fn is_block(&self) -> bool {
+        true
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_document(self);
+        for child in &self.children {
+            child.accept(visitor);
+        }
+        #[cfg(feature = "gfm")]
+        for footnote in &self.footnotes {
+            footnote.accept(visitor);
+        }
+    }
+}
+//endregion
+
+//region Macro
+/// Builds a [Document](markdown/struct.Document.html) from a list of blocks
+///
+/// Each block ends with `;` and is one of:
+/// * `heading(level, text...)`
+/// * `paragraph(text...)`
+/// * `quote(text...)`
+/// * `link(text, address)` - a paragraph containing a single link
+/// * `code(code)` or `code(language, code)`
+/// * `list![items...]` or `numbered_list![items...]`
+/// * `rule` - a thematic break
+/// * `block(element)` - any other element
+///
+/// # Example
+/// ```
+/// use markdown_gen::markdown;
+/// use markdown_gen::markdown::AsMarkdown;
+///
+/// let document = markdown! {
+///     heading(1, "Title");
+///     paragraph("Some ", "text".bold());
+///     list!["a", "b"];
+/// };
+/// assert_eq!(
+///     markdown::to_markdown_string(document),
+///     "# Title\n\nSome **text**\n\n   * a\n   * b\n\n"
+/// );
+/// ```
+#[macro_export]
+macro_rules! markdown {
+    (@blocks $document:ident) => {
+        $document
+    };
+    (@blocks $document:ident heading($level:expr, $($text:expr),+ $(,)?); $($rest:tt)*) => {{
+        let block = $crate::markdown::Heading::new($level)$(.append($text))+;
+        let $document = $document.append(block);
+        $crate::markdown!(@blocks $document $($rest)*)
+    }};
+    (@blocks $document:ident paragraph($($text:expr),+ $(,)?); $($rest:tt)*) => {{
+        let block = $crate::markdown::Paragraph::new()$(.append($text))+;
+        let $document = $document.append(block);
+        $crate::markdown!(@blocks $document $($rest)*)
+    }};
+    (@blocks $document:ident quote($($text:expr),+ $(,)?); $($rest:tt)*) => {{
+        let block = $crate::markdown::Quote::new()$(.append($text))+;
+        let $document = $document.append(block);
+        $crate::markdown!(@blocks $document $($rest)*)
+    }};
+    (@blocks $document:ident link($text:expr, $address:expr $(,)?); $($rest:tt)*) => {{
+        let block = $crate::markdown::Paragraph::new()
+            .append($crate::markdown::Link::new($address).append($text));
+        let $document = $document.append(block);
+        $crate::markdown!(@blocks $document $($rest)*)
+    }};
+    (@blocks $document:ident code($code:expr $(,)?); $($rest:tt)*) => {{
+        let $document = $document.append($crate::markdown::CodeBlock::new($code));
+        $crate::markdown!(@blocks $document $($rest)*)
+    }};
+    (@blocks $document:ident code($language:expr, $code:expr $(,)?); $($rest:tt)*) => {{
+        let block = $crate::markdown::CodeBlock::new($code).language($language);
+        let $document = $document.append(block);
+        $crate::markdown!(@blocks $document $($rest)*)
+    }};
+    (@blocks $document:ident list![$($item:expr),* $(,)?]; $($rest:tt)*) => {{
+        let block = $crate::markdown::List::new(false)$(.item($item))*;
+        let $document = $document.append(block);
+        $crate::markdown!(@blocks $document $($rest)*)
+    }};
+    (@blocks $document:ident numbered_list![$($item:expr),* $(,)?]; $($rest:tt)*) => {{
+        let block = $crate::markdown::List::new(true)$(.item($item))*;
+        let $document = $document.append(block);
+        $crate::markdown!(@blocks $document $($rest)*)
+    }};
+    (@blocks $document:ident rule; $($rest:tt)*) => {{
+        let $document = $document.append($crate::markdown::ThematicBreak);
+        $crate::markdown!(@blocks $document $($rest)*)
+    }};
+    (@blocks $document:ident block($element:expr $(,)?); $($rest:tt)*) => {{
+        let $document = $document.append($element);
+        $crate::markdown!(@blocks $document $($rest)*)
+    }};
+    ($($blocks:tt)*) => {{
+        let document = $crate::markdown::Document::new();
+        $crate::markdown!(@blocks document $($blocks)*)
+    }};
+}
+//endregion
+
+//region Paragraph
+/// Markdown paragraph
+pub struct Paragraph<'a> {
+    children: Vec<Child<'a>>,
+    wrap: Option<usize>,
+}
+
+impl<'a> Paragraph<'a> {
+    /// Creates an empty paragraph
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Creates an empty paragraph with room for `capacity` elements
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            children: Vec::with_capacity(capacity),
+            wrap: None,
+        }
+    }
+
+    /// Returns the number of elements in the paragraph
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Returns `true` if the paragraph has no elements
+    ///
+    /// Empty paragraphs aren't written.
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Appends an element to the paragraph
+    pub fn append<T: 'a + MarkdownWritable>(mut self, element: T) -> Self {
+        self.children.push(element.into_child());
+        self
+    }
+
+    /// Appends the text representation of `text` to the paragraph
+    ///
+    /// The text is converted to an owned `String` and escaped like any other
+    /// text, e.g. `append_text(42)` appends `42`.
+    pub fn append_text<S: ToString>(self, text: S) -> Self {
+        self.append(text.to_string())
+    }
+
+    /// Appends all elements yielded by `elements` to the paragraph
+    pub fn append_all<T, I>(mut self, elements: I) -> Self
+    where
+        T: 'a + MarkdownWritable,
+        I: IntoIterator<Item = T>,
+    {
+        self.children
+            .extend(elements.into_iter().map(MarkdownWritable::into_child));
+        self
+    }
+
+    /// Appends a [SoftBreak](struct.SoftBreak.html), continuing the paragraph on a new line
+    ///
+    /// Unlike a hard [LineBreak](struct.LineBreak.html), it is rendered as a space.
+    pub fn soft_break(self) -> Self {
+        self.append(SoftBreak)
+    }
+
+    /// Hard-wraps the paragraph at word boundaries
+    ///
+    /// The width includes the line prefix of enclosing quotes and lists.
+    /// Inline code spans and links are never broken, so a line may still
+    /// exceed `width` if a single word or span is longer than it.
+    ///
+    /// # Arguments
+    /// * `width` - Maximum number of characters per line
+    pub fn wrap(mut self, width: usize) -> Self {
+        self.wrap = Some(width);
+        self
+    }
+}
+
+impl Default for Paragraph<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarkdownWritable for Paragraph<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let inner = ctx.is_inner();
+        let escape = ctx.escape();
+        let line_prefix = ctx.line_prefix();
+        let options = ctx.options();
+        match self.wrap {
+            Some(width) => {
+                let mut text = Vec::new();
+                let line = Cell::new(ctx.line_state());
+                for (child, bracket) in with_next_bracket(&self.children, ctx.before_bracket()) {
+                    child.write_to(
+                        &mut RenderContext::new(&mut text, options)
+                            .with_inner(true)
+                            .with_escape(escape)
+                            .with_line(&line)
+                            .with_before_bracket(bracket),
+                    )?;
+                }
+                let prefix_width = line_prefix.map_or(0, |prefix| prefix.len());
+                wrap_lines(&mut text, width.saturating_sub(prefix_width));
+                write_line_prefixed(ctx, &text, line_prefix)?;
+            }
+            None => {
+                for (child, bracket) in with_next_bracket(&self.children, ctx.before_bracket()) {
+                    child.write_to(&mut ctx.child().with_before_bracket(bracket))?;
+                }
+            }
+        }
+        if !inner {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+        }
+        Ok(())
+    }
+
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+        let mut carry = carry;
+        let mut count = 0;
+        for child in &self.children {
+            let (c, cr) = child.count_max_streak(char, carry);
+            count += c;
+            carry = cr;
+        }
+        count += carry;
+        (count, 0)
+    }
+
+    fn is_block(&self) -> bool {
+        true
+    }
+
+    fn is_empty_block(&self) -> bool {
+        self.is_empty()
+    }
+
+    fn starts_with_bracket(&self) -> bool {
+        matches!(self.children.first(), Some(child) if child.starts_with_bracket())
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_paragraph(self);
+        for child in &self.children {
+            child.accept(visitor);
+        }
+    }
+}
+//endregion
+
+//region Span
+/// Inline group of elements
+///
+/// Unlike [Paragraph](struct.Paragraph.html) it is never followed by a blank
+/// line, so it can be appended anywhere an inline element is allowed.
+pub struct Span<'a> {
+    children: Vec<Child<'a>>,
+}
+
+impl<'a> Span<'a> {
+    /// Creates an empty span
+    pub fn new() -> Self {
+        Self {
+            children: Vec::new(),
+        }
+    }
+
+    /// Appends an element to the span
+    pub fn append<T: 'a + MarkdownWritable>(mut self, element: T) -> Self {
+        self.children.push(element.into_child());
+        self
+    }
+
+    /// Appends all elements yielded by `elements` to the span
+    pub fn append_all<T, I>(mut self, elements: I) -> Self
+    where
+        T: 'a + MarkdownWritable,
+        I: IntoIterator<Item = T>,
+    {
+        self.children
+            .extend(elements.into_iter().map(MarkdownWritable::into_child));
+        self
+    }
+}
+
+impl Default for Span<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarkdownWritable for Span<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        for (child, bracket) in with_next_bracket(&self.children, ctx.before_bracket()) {
+            child.write_to(&mut ctx.child().with_before_bracket(bracket))?;
+        }
+        if !inner {
+            write_line_prefixed(ctx, b"\n", line_prefix)?;
+        }
+        Ok(())
+    }
+
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+        let mut carry = carry;
+        let mut count = 0;
+        for child in &self.children {
+            let (c, cr) = child.count_max_streak(char, carry);
+            count += c;
+            carry = cr;
+        }
+        (count, carry)
+    }
+
+    fn starts_with_bracket(&self) -> bool {
+        matches!(self.children.first(), Some(child) if child.starts_with_bracket())
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_span(self);
+        for child in &self.children {
+            child.accept(visitor);
+        }
+    }
+}
+//endregion
+
+//region Heading
+/// Markdown heading
+pub struct Heading<'a> {
+    children: Vec<Child<'a>>,
+    level: usize,
+    anchor: Option<&'a str>,
+    setext: bool,
+}
+
+impl<'a> Heading<'a> {
+    /// Creates an empty heading
+    ///
+    /// # Arguments
+    /// * `level` - Heading level (1-6)
+    pub fn new(level: usize) -> Self {
+        assert!(level > 0 && level <= 6, "Heading level must be range 1-6.");
+        Self {
+            children: Vec::new(),
+            level,
+            anchor: None,
+            setext: false,
+        }
+    }
+
+    /// Creates an empty heading, failing instead of panicking on invalid levels
+    ///
+    /// # Arguments
+    /// * `level` - Heading level (1-6)
+    ///
+    /// # Returns
+    /// The heading or [ValidationError](enum.ValidationError.html) if `level` is out of range
+    pub fn try_new(level: usize) -> Result<Self, ValidationError> {
+        if level > 0 && level <= 6 {
+            Ok(Self::new(level))
+        } else {
+            Err(ValidationError::HeadingLevel(level))
+        }
+    }
+
+    /// Creates an empty heading accepting any level
+    ///
+    /// Level 0 is treated as 1, levels deeper than 6 are written as a bold paragraph.
+    /// Such a paragraph starts with an HTML `<a id>` if an [anchor](#method.anchor)
+    /// is set, so links to it keep working; [setext](#method.setext) has no effect on it.
+    pub fn clamped(level: usize) -> Self {
+        Self {
+            children: Vec::new(),
+            level: level.max(1),
+            anchor: None,
+            setext: false,
+        }
+    }
+
+    /// Appends an element to the heading
+    pub fn append<T: 'a + MarkdownWritable>(mut self, element: T) -> Self {
+        self.children.push(element.into_child());
+        self
+    }
+
+    /// Appends all elements yielded by `elements` to the heading
+    pub fn append_all<T, I>(mut self, elements: I) -> Self
+    where
+        T: 'a + MarkdownWritable,
+        I: IntoIterator<Item = T>,
+    {
+        self.children
+            .extend(elements.into_iter().map(MarkdownWritable::into_child));
+        self
+    }
+
+    /// Writes levels 1 and 2 as setext headings, underlined with `=` or `-`
+    ///
+    /// Levels 3-6 and empty headings are still written with `#`.
+    pub fn setext(mut self) -> Self {
+        self.setext = true;
+        self
+    }
+
+    /// Sets an explicit anchor written as `{#id}` after the heading text
+    #[cfg(feature = "pandoc")]
+    pub fn anchor(mut self, id: &'a str) -> Self {
+        self.anchor = Some(id);
+        self
+    }
+
+    /// Returns the level of the heading
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// Returns the text of the heading rendered as Markdown
+    pub fn text(&self) -> String {
+        self.render_children(Normal, &RenderOptions::new())
+    }
+
+    /// Returns the anchor id of the heading
+    ///
+    /// This is the explicit [anchor](#method.anchor) if set, otherwise the
+    /// GitHub-style slug of the heading text: lowercase, spaces replaced with
+    /// hyphens and punctuation removed.
+    pub fn slug(&self) -> String {
+        match self.anchor {
+            Some(anchor) => anchor.to_string(),
+            None => slugify(&self.render_children(InlineCode, &RenderOptions::new())),
+        }
+    }
+
+    fn render_children(&self, escape: Escaping, options: &RenderOptions) -> String {
+        let mut text = Vec::new();
+        let line = Cell::new(LineState::Start);
+        for (child, bracket) in with_next_bracket(&self.children, false) {
+            child
+                .write_to(
+                    &mut RenderContext::new(&mut text, options)
+                        .with_inner(true)
+                        .with_escape(escape)
+                        .with_line(&line)
+                        .with_before_bracket(bracket),
+                )
+                .expect("Writing to a Vec<u8> cannot fail.");
+        }
+        String::from_utf8_lossy(&text).into_owned()
+    }
+}
+
+impl MarkdownWritable for Heading<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        let options = ctx.options();
+        assert!(!inner, "Inner headings are forbidden.");
+        if self.level > 6 {
+            if let Some(anchor) = self.anchor {
+                Anchor(anchor).write_to(&mut ctx.child())?;
+            }
+            ctx.write_all(b"**")?;
+            for (child, bracket) in with_next_bracket(&self.children, false) {
+                child
+                    .write_to(&mut ctx.child().with_escape(Normal).with_before_bracket(bracket))?;
+            }
+            ctx.write_all(b"**")?;
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+            return Ok(());
+        }
+        if self.setext && self.level <= 2 {
+            let escape = match ctx.escape() {
+                Escaping::None => Escaping::None,
+                _ => Normal,
+            };
+            let mut text = self.render_children(escape, options);
+            if let Some(anchor) = self.anchor {
+                text = format!("{} {{#{}}}", text, anchor);
+            }
+            if !text.trim().is_empty() {
+                let width = text
+                    .rsplit('\n')
+                    .next()
+                    .map_or(0, |line| line.chars().count());
+                let underline = if self.level == 1 { b'=' } else { b'-' };
+                write_line_prefixed(ctx, text.as_bytes(), line_prefix)?;
+                write_line_prefixed(ctx, b"\n", line_prefix)?;
+                ctx.write_all(&vec![underline; width])?;
+                write_line_prefixed(ctx, b"\n", line_prefix)?;
+                return Ok(());
+            }
+        }
+        let mut prefix = Vec::new();
+        prefix.resize(self.level, b'#');
+        prefix.push(b' ');
+        ctx.write_all(&prefix)?;
+        for (child, bracket) in with_next_bracket(&self.children, false) {
+            child.write_to(&mut ctx.child().with_escape(Normal).with_before_bracket(bracket))?;
+        }
+        if let Some(anchor) = self.anchor {
+            ctx.write_all(b" {#")?;
+            ctx.write_all(anchor.as_bytes())?;
+            ctx.write_all(b"}")?;
+        }
+        write_line_prefixed(ctx, b"\n", line_prefix)?;
+        Ok(())
+    }
+
+    fn count_max_streak(&self, char: u8, _carry: usize) -> (usize, usize) {
+        let mut carry = 0;
+        let mut count = 0;
+        for child in &self.children {
+            let (c, cr) = child.count_max_streak(char, carry);
+            count += c;
+            carry = cr;
+        }
+        (count, carry)
+    }
+
+    fn is_block(&self) -> bool {
+        true
+    }
+
+    fn as_heading(&self) -> Option<&Heading<'_>> {
+        Some(self)
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_heading(self);
+        for child in &self.children {
+            child.accept(visitor);
+        }
+    }
+}
+//endregion
+
+//region TableOfContents
+/// Nested list of links to headings
+pub struct TableOfContents {
+    entries: Vec<(usize, String, String)>,
+}
+
+impl TableOfContents {
+    /// Creates a table of contents linking to `headings`
+    ///
+    /// Links lead to [Heading::slug](struct.Heading.html#method.slug), generated
+    /// slugs that repeat are numbered the way GitHub does.
+    pub fn from_headings(headings: &[Heading]) -> Self {
+        Self::collect(headings)
+    }
+
+    fn collect<'h, 'b: 'h, I>(headings: I) -> Self
+    where
+        I: IntoIterator<Item = &'h Heading<'b>>,
+    {
+        let mut used = HashMap::new();
+        let mut entries = Vec::new();
+        for heading in headings {
+            let mut slug = heading.slug();
+            if heading.anchor.is_none() {
+                let count = used.entry(slug.clone()).or_insert(0);
+                if *count > 0 {
+                    slug += format!("-{}", count).as_str();
+                }
+                *count += 1;
+            }
+            entries.push((heading.level, heading.text(), slug));
+        }
+        Self { entries }
+    }
+}
+
+impl MarkdownWritable for TableOfContents {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let line_prefix = ctx.line_prefix();
+        let mut prefix = Vec::new();
+        if let Some(line_prefix) = line_prefix {
+            prefix.extend_from_slice(line_prefix);
+        }
+        let base = prefix.len();
+
+        // Levels of the headings enclosing the current entry
+        let mut parents: Vec<usize> = Vec::new();
+        for (level, text, slug) in &self.entries {
+            while parents.last().is_some_and(|parent| parent >= level) {
+                parents.pop();
+            }
+            prefix.resize(base + 3 * (parents.len() + 1), b' ');
+            parents.push(*level);
+
+            write_line_prefixed(ctx, b"\n* [", Some(&prefix))?;
+            ctx.write_all(text.as_bytes())?;
+            ctx.write_all(b"](#")?;
+            ctx.write_all(slug.as_bytes())?;
+            ctx.write_all(b")")?;
+        }
+        Ok(())
+    }
+
+    fn count_max_streak(&self, char: u8, _carry: usize) -> (usize, usize) {
+        let mut count = 0;
+        for (_, text, _) in &self.entries {
+            let (c, cr) = text.as_str().count_max_streak(char, 0);
+            count = count.max(c).max(cr);
+        }
+        (count, 0)
+    }
+
+    fn is_block(&self) -> bool {
+        true
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_table_of_contents(self);
+    }
+}
+//endregion
+
+//region Table
+/// Horizontal alignment of a table column
+#[derive(Clone, Copy)]
+#[non_exhaustive]
+pub enum Alignment {
+    /// Column content is aligned to the left
+    Left,
+    /// Column content is centered
+    Center,
+    /// Column content is aligned to the right
+    Right,
+    /// No explicit alignment, the renderer decides
+    None,
+}
+
+impl Alignment {
+    /// Returns the delimiter row cell, at least `width` characters wide
+    ///
+    /// It always has at least 3 dashes.
+    fn delimiter(self, width: usize) -> Vec<u8> {
+        let (left, right) = match self {
+            Alignment::Left => (true, false),
+            Alignment::Center => (true, true),
+            Alignment::Right => (false, true),
+            Alignment::None => (false, false),
+        };
+        let dashes = width.saturating_sub(left as usize + right as usize).max(3);
+        let mut delimiter = Vec::new();
+        if left {
+            delimiter.push(b':');
+        }
+        delimiter.resize(delimiter.len() + dashes, b'-');
+        if right {
+            delimiter.push(b':');
+        }
+        delimiter
+    }
+
+    fn style(self) -> &'static str {
+        match self {
+            Alignment::Left => " style=\"text-align:left\"",
+            Alignment::Center => " style=\"text-align:center\"",
+            Alignment::Right => " style=\"text-align:right\"",
+            Alignment::None => "",
+        }
+    }
+}
+
+/// Markdown Table
+pub struct Table<'a> {
+    gfm: bool,
+    columns: Vec<&'a str>,
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<Child<'a>>>,
+    normalize_rows: bool,
+    strict: bool,
+    outer_pipes: bool,
+    pretty: bool,
+}
+
+impl<'a> Table<'a> {
+    /// Creates an empty table
+    ///
+    /// # Arguments
+    /// * `gfm` - check to use GitHub Flavored Markdown Spec (supports HTML)
+    ///   if not use default spec
+    pub fn new(gfm: bool) -> Self {
+        Self {
+            gfm,
+            columns: vec![],
+            alignments: vec![],
+            rows: vec![vec![]],
+            normalize_rows: false,
+            strict: false,
+            outer_pipes: true,
+            pretty: false,
+        }
+    }
+
+    /// Add headers to table
+    ///
+    /// Tables written without the `gfm` HTML syntax need at least one column,
+    /// otherwise writing them fails with an error of kind `InvalidInput`.
+    pub fn header(mut self, columns: Vec<&'a str>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Sets alignment of the columns, one entry per column
+    ///
+    /// Columns without an entry default to [Alignment::None](enum.Alignment.html#variant.None)
+    pub fn alignments(mut self, aligns: Vec<Alignment>) -> Self {
+        self.alignments = aligns;
+        self
+    }
+
+    /// Sets the rows of the table
+    ///
+    /// Use [row](#method.row) for cells containing other inline elements.
+    pub fn rows(mut self, rows: Vec<Vec<String>>) -> Self {
+        self.rows = rows
+            .into_iter()
+            .map(|row| row.into_iter().map(MarkdownWritable::into_child).collect())
+            .collect();
+        self
+    }
+
+    /// Sets the rows of the table, converting every cell with `to_string`
+    ///
+    /// # Example
+    /// ```
+    /// use markdown_gen::markdown::{to_markdown_string, Table};
+    ///
+    /// let table = Table::new(false)
+    ///     .header(vec!["n", "n²"])
+    ///     .rows_from((1..=2).map(|n| [n, n * n]));
+    /// assert_eq!(
+    ///     to_markdown_string(table),
+    ///     "| n | n² |\n| --- | --- |\n| 1 | 1 |\n| 2 | 4 |\n\n"
+    /// );
+    /// ```
+    pub fn rows_from<I, R, C>(mut self, rows: I) -> Self
+    where
+        I: IntoIterator<Item = R>,
+        R: IntoIterator<Item = C>,
+        C: fmt::Display,
+    {
+        self.rows = rows
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|cell| cell.to_string().into_child())
+                    .collect()
+            })
+            .collect();
+        self
+    }
+
+    /// Appends a row to the table
+    pub fn row(mut self, row: TableRow<'a>) -> Self {
+        self.rows.push(row.cells);
+        self
+    }
+
+    /// Pads short rows with empty cells and truncates long rows
+    ///
+    /// When disabled (default), rows are written with the cells they have.
+    pub fn normalize_rows(mut self, normalize: bool) -> Self {
+        self.normalize_rows = normalize;
+        self
+    }
+
+    /// Sets whether writing a table whose rows don't match the header fails
+    ///
+    /// When enabled, writing fails with the error returned by
+    /// [validate](#method.validate), unless rows are normalized by
+    /// [normalize_rows](#method.normalize_rows). Disabled by default.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets whether rows start and end with a `|`, e.g. `| a | b |` or `a | b`
+    ///
+    /// Enabled by default for maximum compatibility. Has no effect on HTML
+    /// tables. Pipes are still written around single column tables and rows
+    /// with an empty first or last cell, which would be misread without them.
+    pub fn outer_pipes(mut self, outer_pipes: bool) -> Self {
+        self.outer_pipes = outer_pipes;
+        self
+    }
+
+    /// Pads cells with spaces so the columns line up in the Markdown source
+    ///
+    /// Each column is as wide as its widest cell, the delimiter row included.
+    /// Width is counted in characters, so wide characters such as CJK
+    /// ideographs may still be slightly misaligned. Has no effect on HTML tables.
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// Checks that every row has as many cells as the header has columns
+    ///
+    /// Empty rows and tables without a header are always valid.
+    ///
+    /// # Returns
+    /// `()` or [ValidationError::TableRow](enum.ValidationError.html#variant.TableRow)
+    /// describing the first mismatched row
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.columns.is_empty() {
+            return Ok(());
+        }
+        for (i, row) in self.rows.iter().enumerate() {
+            if !row.is_empty() && row.len() != self.columns.len() {
+                return Err(ValidationError::TableRow {
+                    row: i,
+                    cells: row.len(),
+                    columns: self.columns.len(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn row_cells<'r>(&self, row: &'r [Child<'_>]) -> Vec<&'r dyn MarkdownWritable> {
+        let mut cells: Vec<&dyn MarkdownWritable> = row
+            .iter()
+            .map(|cell| cell as &dyn MarkdownWritable)
+            .collect();
+        if self.normalize_rows && !self.columns.is_empty() && !row.is_empty() {
+            cells.resize(self.columns.len(), &"");
+        }
+        cells
+    }
+
+    /// Writes a row of rendered cells, padding them to `widths` if given
+    fn write_row(
+        &self,
+        ctx: &mut RenderContext,
+        cells: &[Vec<u8>],
+        widths: &[usize],
+        outer_pipes: bool,
+    ) -> Result<(), Error> {
+        let line_prefix = ctx.line_prefix();
+        // Without the pipe an empty edge cell would vanish
+        let blank = |cell: Option<&Vec<u8>>| cell.is_some_and(|cell| cell.trim_ascii().is_empty());
+        let outer_pipes = outer_pipes || blank(cells.first()) || blank(cells.last());
+        if outer_pipes {
+            ctx.write_all(b"| ")?;
+        }
+        for (k, cell) in cells.iter().enumerate() {
+            if k > 0 {
+                ctx.write_all(b" | ")?;
+            }
+            let padding = widths
+                .get(k)
+                .map_or(0, |width| width.saturating_sub(text_width(cell)));
+            let before = match self.alignment(k) {
+                Alignment::Right => padding,
+                Alignment::Center => padding / 2,
+                Alignment::Left | Alignment::None => 0,
+            };
+            // Trailing padding of the last cell is only needed before a pipe
+            let after = if k + 1 < cells.len() || outer_pipes {
+                padding - before
+            } else {
+                0
+            };
+            ctx.write_all(&vec![b' '; before])?;
+            write_line_prefixed(ctx, cell, line_prefix)?;
+            ctx.write_all(&vec![b' '; after])?;
+        }
+        if outer_pipes {
+            ctx.write_all(b" |")?;
+        }
+        Ok(())
+    }
+
+    fn alignment(&self, column: usize) -> Alignment {
+        self.alignments
+            .get(column)
+            .copied()
+            .unwrap_or(Alignment::None)
+    }
+}
+
+impl MarkdownWritable for Table<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        if self.strict && !self.normalize_rows {
+            self.validate()?;
+        }
+
+        // Check if is GitHub Flavored Markdown Spec
+        match self.gfm {
+            true => {
+                ctx.write_all(b"<table>")?;
+                if !self.columns.is_empty() {
+                    ctx.write_all(b"<thead><tr>")?;
+                    for (k, column) in self.columns.iter().enumerate() {
+                        write_html_cell(ctx, "th", self.alignment(k), column)?;
+                    }
+                    ctx.write_all(b"</tr></thead>")?;
+                }
+                ctx.write_all(b"<tbody>")?;
+
+                for row in &self.rows {
+                    let cells = self.row_cells(row);
+                    for (r, cell) in cells.iter().enumerate() {
+                        if r == 0 {
+                            ctx.write_all(b"<tr>")?;
+                        }
+                        write_html_cell(ctx, "td", self.alignment(r), *cell)?;
+                    }
+                    if !cells.is_empty() {
+                        ctx.write_all(b"</tr>")?;
+                    }
+                }
+                ctx.write_all(b"</tbody></table>")?;
+            }
+            false => {
+                // A pipe table without columns has no delimiter row and would
+                // not be recognized as a table
+                if self.columns.is_empty() {
+                    return Err(ValidationError::TableWithoutColumns.into());
+                }
+                let options = ctx.options();
+                let escape = match ctx.escape() {
+                    Escaping::None => Escaping::None,
+                    _ => Normal,
+                };
+                let header = self
+                    .columns
+                    .iter()
+                    .map(|column| render_table_cell(column, escape, options))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut rows = Vec::new();
+                for row in self.rows.iter().filter(|row| !row.is_empty()) {
+                    rows.push(
+                        self.row_cells(row)
+                            .into_iter()
+                            .map(|cell| render_table_cell(cell, escape, options))
+                            .collect::<Result<Vec<_>, _>>()?,
+                    );
+                }
+                let mut widths = Vec::new();
+                if self.pretty {
+                    for row in std::iter::once(&header).chain(&rows) {
+                        for (k, cell) in row.iter().enumerate() {
+                            if widths.len() <= k {
+                                widths.push(self.alignment(k).delimiter(0).len());
+                            }
+                            widths[k] = widths[k].max(text_width(cell));
+                        }
+                    }
+                }
+                let delimiters: Vec<Vec<u8>> = (0..self.columns.len())
+                    .map(|k| {
+                        self.alignment(k)
+                            .delimiter(widths.get(k).copied().unwrap_or(0))
+                    })
+                    .collect();
+
+                let outer_pipes = self.outer_pipes || self.columns.len() < 2;
+                self.write_row(ctx, &header, &widths, outer_pipes)?;
+                write_line_prefixed(ctx, b"\n", line_prefix)?;
+                self.write_row(ctx, &delimiters, &widths, outer_pipes)?;
+                for row in &rows {
+                    write_line_prefixed(ctx, b"\n", line_prefix)?;
+                    self.write_row(ctx, row, &widths, outer_pipes)?;
+                }
+            }
+        }
+
+        if !inner {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+        }
+        Ok(())
+    }
+
+    fn count_max_streak(&self, _: u8, _carry: usize) -> (usize, usize) {
+        (0, 0)
+    }
+
+    fn is_block(&self) -> bool {
+        true
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_table(self);
+        for column in &self.columns {
+            visitor.visit_text(column);
+        }
+        for cell in self.rows.iter().flatten() {
+            cell.accept(visitor);
+        }
+    }
+}
+
+/// Row of a [Table](struct.Table.html) whose cells may be different inline elements
+pub struct TableRow<'a> {
+    cells: Vec<Child<'a>>,
+}
+
+impl<'a> TableRow<'a> {
+    /// Creates an empty row
+    pub fn new() -> Self {
+        Self { cells: Vec::new() }
+    }
+
+    /// Appends a cell to the row
+    pub fn cell<T: 'a + MarkdownWritable>(mut self, cell: T) -> Self {
+        self.cells.push(cell.into_child());
+        self
+    }
+}
+
+impl Default for TableRow<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+//endregion
+
+//region Link
+/// Markdown link
+pub struct Link<'a> {
+    children: Vec<Child<'a>>,
+    address: &'a str,
+    title: Option<&'a str>,
+}
+
+impl<'a> Link<'a> {
+    /// Creates an empty link, which leads to `address`
+    ///
+    /// A link left without text shows its address: absolute URLs without a
+    /// title are written as an autolink `<address>`, anything else as
+    /// `[address](address)`.
+    pub fn new(address: &'a str) -> Self {
+        Self {
+            children: Vec::new(),
+            address,
+            title: None,
+        }
+    }
+
+    /// Creates a link with `text`, which leads to `address`
+    ///
+    /// Shorthand for `Link::new(address).append(text)`, e.g. for text built
+    /// elsewhere.
+    pub fn with_text<T: 'a + MarkdownWritable>(text: T, address: &'a str) -> Self {
+        Self::new(address).append(text)
+    }
+
+    /// Sets the title of the link, usually shown when hovering over it
+    pub fn title(mut self, t: &'a str) -> Self {
+        self.title = Some(t);
+        self
+    }
+
+    /// Appends an element to the link's text
+    pub fn append<T: 'a + MarkdownWritable>(mut self, element: T) -> Self {
+        self.children.push(element.into_child());
+        self
+    }
+}
+
+impl MarkdownWritable for Link<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let escape = ctx.escape();
+        let line_prefix = ctx.line_prefix();
+        if let Html = escape {
+            ctx.write_all(b"<a href=\"")?;
+            self.address.write_to(&mut ctx.child())?;
+            if let Some(title) = self.title {
+                ctx.write_all(b"\" title=\"")?;
+                title.write_to(&mut ctx.child())?;
+            }
+            ctx.write_all(b"\">")?;
+            if self.children.is_empty() {
+                self.address.write_to(&mut ctx.child())?;
+            }
+            for child in &self.children {
+                child.write_to(&mut ctx.child())?;
+            }
+            ctx.write_all(b"</a>")?;
+            if !inner {
+                write_line_prefixed(ctx, b"\n", line_prefix)?;
+            }
+            return Ok(());
+        }
+        if self.children.is_empty() {
+            // Without text the link couldn't be clicked, so the address is shown instead
+            if self.title.is_none() && matches!(escape, Normal) && is_autolink_url(self.address) {
+                Autolink::new(self.address).write_to(&mut ctx.child())?;
+                if !inner {
+                    write_line_prefixed(ctx, b"\n", line_prefix)?;
+                }
+                return Ok(());
+            }
+            ctx.write_all(b"[")?;
+            self.address.write_to(&mut ctx.child())?;
+        } else {
+            ctx.write_all(b"[")?;
+            for child in &self.children {
+                child.write_to(&mut ctx.child())?;
+            }
+        }
+        ctx.write_all(b"](")?;
+        write_link_destination(ctx, self.address)?;
+        if let Some(title) = self.title {
+            write_link_title(ctx, title, line_prefix)?;
+        }
+        ctx.write_all(b")")?;
+        if !inner {
+            write_line_prefixed(ctx, b"\n", line_prefix)?;
+        }
+        Ok(())
+    }
+
+    fn count_max_streak(&self, char: u8, _carry: usize) -> (usize, usize) {
+        let (mut addr, addr_cr) = self.address.count_max_streak(char, 0);
+        addr += addr_cr;
+        let mut carry = 0;
+        let mut count = 0;
+        for child in &self.children {
+            let (c, cr) = child.count_max_streak(char, carry);
+            count += c;
+            carry = cr;
+        }
+        count += carry;
+        if count > addr {
+            (count, 0)
+        } else {
+            (addr, 0)
+        }
+    }
+
+    fn starts_with_bracket(&self) -> bool {
+        true
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_link(self);
+        for child in &self.children {
+            child.accept(visitor);
+        }
+    }
+}
+
+impl<'a> AsMarkdown<'a> for &'a Link<'a> {
+    fn paragraph(self) -> Paragraph<'a> {
+        Paragraph::new().append(self)
+    }
+
+    fn heading(self, level: usize) -> Heading<'a> {
+        Heading::new(level).append(self)
+    }
+
+    fn link_to(self, address: &'a str) -> Link<'a> {
+        self.try_link_to(address)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_link_to(self, _address: &'a str) -> Result<Link<'a>, ValidationError> {
+        Err(ValidationError::Conversion(
+            "Link cannot contain another link.",
+        ))
+    }
+
+    fn bold(self) -> RichText<'a> {
+        self.try_bold().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_bold(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion(
+            "Cannot change link's body. Please use 'x.as_bold().as_link_to(...);'",
+        ))
+    }
+
+    fn italic(self) -> RichText<'a> {
+        self.try_italic().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_italic(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion(
+            "Cannot change link's body. Please use 'x.as_italic().as_link_to(...);'",
+        ))
+    }
+
+    fn code(self) -> RichText<'a> {
+        self.try_code().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_code(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion(
+            "Cannot change link's body. Please use 'x.as_code().as_link_to(...);'",
+        ))
+    }
+
+    #[cfg(feature = "pandoc")]
+    fn try_highlight(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion(
+            "Cannot change link's body. Please use 'x.as_highlight().as_link_to(...);'",
+        ))
+    }
+
+    fn quote(self) -> Quote<'a> {
+        Quote::new().append(self)
+    }
+}
+
+impl<'a> AsMarkdown<'a> for Link<'a> {
+    fn paragraph(self) -> Paragraph<'a> {
+        Paragraph::new().append(self)
+    }
+
+    fn heading(self, level: usize) -> Heading<'a> {
+        Heading::new(level).append(self)
+    }
+
+    fn link_to(self, address: &'a str) -> Link<'a> {
+        self.try_link_to(address)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_link_to(self, _address: &'a str) -> Result<Link<'a>, ValidationError> {
+        Err(ValidationError::Conversion(
+            "Link cannot contain another link.",
+        ))
+    }
+
+    fn bold(self) -> RichText<'a> {
+        self.try_bold().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_bold(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion(
+            "Cannot change link's body. Please use 'x.as_bold().as_link_to(...);'",
+        ))
+    }
+
+    fn italic(self) -> RichText<'a> {
+        self.try_italic().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_italic(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion(
+            "Cannot change link's body. Please use 'x.as_italic().as_link_to(...);'",
+        ))
+    }
+
+    fn code(self) -> RichText<'a> {
+        self.try_code().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_code(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion(
+            "Cannot change link's body. Please use 'x.as_code().as_link_to(...);'",
+        ))
+    }
+
+    #[cfg(feature = "pandoc")]
+    fn try_highlight(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion(
+            "Cannot change link's body. Please use 'x.as_highlight().as_link_to(...);'",
+        ))
+    }
+
+    fn quote(self) -> Quote<'a> {
+        Quote::new().append(self)
+    }
+}
+//endregion
+
+//region Autolink
+/// Autolink to an absolute URL or an email address, e.g. `<https://example.com>`
+///
+/// Targets which aren't a valid URL or email address are written as escaped
+/// text instead.
+pub struct Autolink<'a> {
+    target: &'a str,
+}
+
+impl<'a> Autolink<'a> {
+    /// Creates an autolink to `target`
+    pub fn new(target: &'a str) -> Self {
+        Self { target }
+    }
+
+    /// Returns `true` if the target can be written as an autolink
+    pub fn is_valid(&self) -> bool {
+        is_autolink_url(self.target) || is_autolink_email(self.target)
+    }
+}
+
+impl MarkdownWritable for Autolink<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        if self.is_valid() && matches!(ctx.escape(), Html) {
+            ctx.write_all(b"<a href=\"")?;
+            if !is_autolink_url(self.target) {
+                ctx.write_all(b"mailto:")?;
+            }
+            self.target.write_to(&mut ctx.child())?;
+            ctx.write_all(b"\">")?;
+            self.target.write_to(&mut ctx.child())?;
+            ctx.write_all(b"</a>")?;
+        } else if self.is_valid() {
+            ctx.write_all(b"<")?;
+            ctx.write_all(self.target.as_bytes())?;
+            ctx.write_all(b">")?;
+        } else {
+            self.target.write_to(&mut ctx.child())?;
+        }
+        if !inner {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+        }
+        Ok(())
+    }
+
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+        self.target.count_max_streak(char, carry)
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_autolink(self);
+    }
+}
+
+/// Checks for an absolute URI as defined by CommonMark: a scheme of 2-32
+/// characters, a colon and no spaces, control characters or angle brackets
+fn is_autolink_url(target: &str) -> bool {
+    let (scheme, rest) = match target.find(':') {
+        Some(colon) => (&target[..colon], &target[colon + 1..]),
+        None => return false,
+    };
+    let mut scheme_chars = scheme.bytes();
+    (2..=32).contains(&scheme.len())
+        && scheme_chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && scheme_chars.all(|c| c.is_ascii_alphanumeric() || b"+.-".contains(&c))
+        && !rest.is_empty()
+        && rest
+            .chars()
+            .all(|c| !c.is_control() && !c.is_whitespace() && c != '<' && c != '>')
+}
+
+/// Checks for an email address as defined by CommonMark autolinks
+fn is_autolink_email(target: &str) -> bool {
+    let (local, domain) = match target.split_once('@') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    !local.is_empty()
+        && local
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || b".!#$%&'*+/=?^_`{|}~-".contains(&c))
+        && domain.split('.').all(|label| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|c| c.is_ascii_alphanumeric() || c == b'-')
+        })
+}
+//endregion
+
+//region ReferenceTable
+/// Shared table of link addresses used by reference-style links
+///
+/// Write the table itself after the links to emit the `[n]: address` definitions.
+pub struct ReferenceTable<'a> {
+    addresses: RefCell<Vec<(&'a str, Option<&'a str>)>>,
+}
+
+impl<'a> ReferenceTable<'a> {
+    /// Creates an empty reference table
+    pub fn new() -> Self {
+        Self {
+            addresses: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Creates an empty reference-style link, which leads to `address`
+    ///
+    /// Links to the same address share one definition
+    pub fn link(&self, address: &'a str) -> ReferenceLink<'a> {
+        self.register(address, None)
+    }
+
+    /// Creates an empty reference-style link, which leads to `address` and
+    /// has a `title` written in its definition
+    ///
+    /// Links to the same address with the same title share one definition
+    pub fn titled_link(&self, address: &'a str, title: &'a str) -> ReferenceLink<'a> {
+        self.register(address, Some(title))
+    }
+
+    fn register(&self, address: &'a str, title: Option<&'a str>) -> ReferenceLink<'a> {
+        let mut addresses = self.addresses.borrow_mut();
+        let index = match addresses.iter().position(|x| *x == (address, title)) {
+            Some(index) => index,
+            None => {
+                addresses.push((address, title));
+                addresses.len() - 1
+            }
+        };
+        ReferenceLink {
+            children: Vec::new(),
+            id: index + 1,
+        }
+    }
+}
+
+impl Default for ReferenceTable<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarkdownWritable for ReferenceTable<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        let addresses = self.addresses.borrow();
+        for (i, (address, title)) in addresses.iter().enumerate() {
+            if i > 0 {
+                write_line_prefixed(ctx, b"\n", line_prefix)?;
+            }
+            ctx.write_all(format!("[{}]: ", i + 1).as_bytes())?;
+            write_link_destination(&mut ctx.child().with_escape(Normal), address)?;
+            if let Some(title) = title {
+                write_link_title(ctx, title, line_prefix)?;
+            }
+        }
+        if !inner && !addresses.is_empty() {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+        }
+        Ok(())
+    }
+
+    fn count_max_streak(&self, _: u8, _carry: usize) -> (usize, usize) {
+        (0, 0)
+    }
+
+    fn is_block(&self) -> bool {
+        true
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_reference_table(self);
+    }
+}
+
+/// Reference-style link created by a [ReferenceTable](struct.ReferenceTable.html)
+pub struct ReferenceLink<'a> {
+    children: Vec<Child<'a>>,
+    id: usize,
+}
+
+impl<'a> ReferenceLink<'a> {
+    /// Appends an element to the link's text
+    pub fn append<T: 'a + MarkdownWritable>(mut self, element: T) -> Self {
+        self.children.push(element.into_child());
+        self
+    }
+}
+
+impl MarkdownWritable for ReferenceLink<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        // HTML can't refer to the link definition, so only the text is written
+        if let Html = ctx.escape() {
+            for child in &self.children {
+                child.write_to(&mut ctx.child())?;
+            }
+        } else {
+            ctx.write_all(b"[")?;
+            for child in &self.children {
+                child.write_to(&mut ctx.child())?;
+            }
+            ctx.write_all(format!("][{}]", self.id).as_bytes())?;
+        }
+        if !inner {
+            write_line_prefixed(ctx, b"\n", line_prefix)?;
+        }
+        Ok(())
+    }
+
+    fn count_max_streak(&self, char: u8, _carry: usize) -> (usize, usize) {
+        let mut carry = 0;
+        let mut count = 0;
+        for child in &self.children {
+            let (c, cr) = child.count_max_streak(char, carry);
+            count += c;
+            carry = cr;
+        }
+        (count + carry, 0)
+    }
+
+    fn starts_with_bracket(&self) -> bool {
+        true
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_reference_link(self);
+        for child in &self.children {
+            child.accept(visitor);
+        }
+    }
+}
+//endregion
+
+//region Image
+/// Markdown image
+pub struct Image<'a> {
+    address: &'a str,
+    alt: &'a str,
+    title: Option<&'a str>,
+}
+
+impl<'a> Image<'a> {
+    /// Creates an image without alternative text, loaded from `address`
+    pub fn new(address: &'a str) -> Self {
+        Self {
+            address,
+            alt: "",
+            title: None,
+        }
+    }
+
+    /// Sets the alternative text of the image
+    pub fn alt(mut self, text: &'a str) -> Self {
+        self.alt = text;
+        self
+    }
+
+    /// Sets the title of the image
+    pub fn title(mut self, t: &'a str) -> Self {
+        self.title = Some(t);
+        self
+    }
+}
+
+impl MarkdownWritable for Image<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        if let Html = ctx.escape() {
+            ctx.write_all(b"<img src=\"")?;
+            self.address.write_to(&mut ctx.child())?;
+            ctx.write_all(b"\" alt=\"")?;
+            self.alt.write_to(&mut ctx.child())?;
+            if let Some(title) = self.title {
+                ctx.write_all(b"\" title=\"")?;
+                title.write_to(&mut ctx.child())?;
+            }
+            ctx.write_all(b"\">")?;
+        } else {
+            ctx.write_all(b"![")?;
+            self.alt.write_to(&mut ctx.child().with_escape(Normal))?;
+            ctx.write_all(b"](")?;
+            write_link_destination(&mut ctx.child().with_escape(Normal), self.address)?;
+            if let Some(title) = self.title {
+                write_link_title(ctx, title, line_prefix)?;
+            }
+            ctx.write_all(b")")?;
+        }
+        if !inner {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+        }
+        Ok(())
+    }
+
+    fn count_max_streak(&self, char: u8, _carry: usize) -> (usize, usize) {
+        let (alt, alt_cr) = self.alt.count_max_streak(char, 0);
+        let (addr, addr_cr) = self.address.count_max_streak(char, 0);
+        ((alt + alt_cr).max(addr + addr_cr), 0)
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_image(self);
+    }
+}
+
+impl<'a> AsMarkdown<'a> for Image<'a> {
+    fn paragraph(self) -> Paragraph<'a> {
+        Paragraph::new().append(self)
+    }
+
+    fn heading(self, level: usize) -> Heading<'a> {
+        Heading::new(level).append(self)
+    }
+
+    fn link_to(self, address: &'a str) -> Link<'a> {
+        Link::new(address).append(self)
+    }
+
+    fn bold(self) -> RichText<'a> {
+        self.try_bold().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_bold(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion("Cannot make an Image bold"))
+    }
+
+    fn italic(self) -> RichText<'a> {
+        self.try_italic().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_italic(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion("Cannot make an Image italic"))
+    }
+
+    fn code(self) -> RichText<'a> {
+        self.try_code().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_code(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion("Cannot make an Image code"))
+    }
+
+    #[cfg(feature = "pandoc")]
+    fn try_highlight(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion(
+            "Cannot make an Image highlighted",
+        ))
+    }
+
+    fn quote(self) -> Quote<'a> {
+        Quote::new().append(self)
+    }
+}
+//endregion
+
+//region RichText
+/// Text styled with **bold**, *italic*, `code` or ==highlight==
+#[derive(Copy, Clone)]
+pub struct RichText<'a> {
+    bold: bool,
+    italic: bool,
+    code: bool,
+    highlight: bool,
+    emphasis: Option<u8>,
+    text: &'a str,
+}
+
+impl<'a> RichText<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            bold: false,
+            italic: false,
+            code: false,
+            highlight: false,
+            emphasis: None,
+            text,
+        }
+    }
+
+    /// Sets the character used for **bold** and *italic* markers
+    ///
+    /// Defaults to [RenderOptions::emphasis](struct.RenderOptions.html#method.emphasis)
+    ///
+    /// # Arguments
+    /// * `marker` - Either `'*'` or `'_'`
+    pub fn emphasis(mut self, marker: char) -> Self {
+        assert!(
+            marker == '*' || marker == '_',
+            "Emphasis marker must be '*' or '_'."
+        );
+        self.emphasis = Some(marker as u8);
+        self
+    }
+}
+
+impl MarkdownWritable for RichText<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let mut escape = ctx.escape();
+        let line_prefix = ctx.line_prefix();
+        let options = ctx.options();
+        if let Html = escape {
+            let tags = [
+                (self.highlight, "mark"),
+                (self.bold, "strong"),
+                (self.italic, "em"),
+                (self.code, "code"),
+            ];
+            for (_, tag) in tags.iter().filter(|(set, _)| *set) {
+                ctx.write_all(format!("<{}>", tag).as_bytes())?;
+            }
+            self.text.write_to(&mut ctx.child())?;
+            for (_, tag) in tags.iter().rev().filter(|(set, _)| *set) {
+                ctx.write_all(format!("</{}>", tag).as_bytes())?;
+            }
+        } else {
+            let emphasis = self.emphasis.unwrap_or(options.emphasis);
+            let mut symbol = Vec::new();
+            if self.highlight {
+                symbol.extend_from_slice(b"==");
+            }
+            if self.bold {
+                symbol.extend_from_slice(&[emphasis; 2]);
+            }
+            if self.italic {
+                // `***` is ambiguous, nest distinct markers instead
+                match (self.bold, emphasis) {
+                    (true, b'*') => symbol.push(b'_'),
+                    (true, _) => symbol.push(b'*'),
+                    (false, _) => symbol.push(emphasis),
+                }
+            }
+            if self.code {
+                if self.text.contains('\n') {
+                    return Err(ValidationError::LineBreakInCode.into());
+                }
+                symbol.extend(vec![b'`'; backticks_needed(self.text)]);
+                symbol.push(b' ');
+                escape = InlineCode;
+            }
+
+            ctx.write_all(&symbol)?;
+            self.text.write_to(&mut ctx.child().with_escape(escape))?;
+            symbol.reverse();
+            ctx.write_all(&symbol)?;
+        }
+
+        if !inner {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+        }
+        Ok(())
+    }
+
+    fn count_max_streak(&self, char: u8, _carry: usize) -> (usize, usize) {
+        let (res, cr) = self.text.count_max_streak(char, 0);
+        (res + cr, 0)
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_rich_text(self);
+        visitor.visit_text(self.text);
+    }
+}
+
+impl<'a> AsMarkdown<'a> for &'a RichText<'a> {
+    fn paragraph(self) -> Paragraph<'a> {
+        Paragraph::new().append(self)
+    }
+
+    fn heading(self, level: usize) -> Heading<'a> {
+        Heading::new(level).append(self)
+    }
+
+    fn link_to(self, address: &'a str) -> Link<'a> {
+        Link::new(address).append(self)
+    }
+
+    fn bold(self) -> RichText<'a> {
+        let mut clone = *self;
+        clone.bold = true;
+        clone
+    }
+
+    fn italic(self) -> RichText<'a> {
+        let mut clone = *self;
+        clone.italic = true;
+        clone
+    }
+
+    fn code(self) -> RichText<'a> {
+        let mut clone = *self;
+        clone.code = true;
+        clone
+    }
+
+    #[cfg(feature = "pandoc")]
+    fn try_highlight(self) -> Result<RichText<'a>, ValidationError> {
+        let mut clone = *self;
+        clone.highlight = true;
+        Ok(clone)
+    }
+
+    fn quote(self) -> Quote<'a> {
+        Quote::new().append(self)
+    }
+}
+
+impl<'a> AsMarkdown<'a> for RichText<'a> {
+    fn paragraph(self) -> Paragraph<'a> {
+        Paragraph::new().append(self)
+    }
+
+    fn heading(self, level: usize) -> Heading<'a> {
+        Heading::new(level).append(self)
+    }
+
+    fn link_to(self, address: &'a str) -> Link<'a> {
+        Link::new(address).append(self)
+    }
+
+    fn bold(mut self) -> RichText<'a> {
+        self.bold = true;
+        self
+    }
+
+    fn italic(mut self) -> RichText<'a> {
+        self.italic = true;
+        self
+    }
+
+    fn code(mut self) -> RichText<'a> {
+        self.code = true;
+        self
+    }
+
+    #[cfg(feature = "pandoc")]
+    fn try_highlight(mut self) -> Result<RichText<'a>, ValidationError> {
+        self.highlight = true;
+        Ok(self)
+    }
+
+    fn quote(self) -> Quote<'a> {
+        Quote::new().append(self)
+    }
+}
+//endregion
+
+//region List
+/// Bulleted or numbered list
+pub struct List<'a> {
+    title: Vec<Child<'a>>,
+    items: Vec<(Option<bool>, Child<'a>)>,
+    numbered: bool,
+    start: usize,
+    indent: Option<usize>,
+    bullet: Option<u8>,
+}
+
+impl<'a> List<'a> {
+    /// Creates an empty list
+    /// # Arguments
+    /// * `numbered` - `true` for numbered list, `false` for bulleted list
+    pub fn new(numbered: bool) -> Self {
+        Self::with_capacity(numbered, 0)
+    }
+
+    /// Creates an empty list with room for `capacity` items
+    /// # Arguments
+    /// * `numbered` - `true` for numbered list, `false` for bulleted list
+    /// * `capacity` - Number of items which can be added without reallocating
+    pub fn with_capacity(numbered: bool, capacity: usize) -> Self {
+        Self {
+            items: Vec::with_capacity(capacity),
+            title: Vec::new(),
+            numbered,
+            start: 1,
+            indent: None,
+            bullet: None,
+        }
+    }
+
+    /// Returns the number of items in the list
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if the list has no items
+    ///
+    /// Only the title of a list without items is written.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Sets the marker used by a bulleted list
+    ///
+    /// Defaults to [RenderOptions::bullet](struct.RenderOptions.html#method.bullet).
+    /// Has no effect on numbered lists.
+    ///
+    /// # Arguments
+    /// * `marker` - One of `-`, `*` or `+`
+    pub fn bullet(mut self, marker: char) -> Self {
+        assert!(
+            matches!(marker, '-' | '*' | '+'),
+            "List bullet must be one of '-', '*' or '+'."
+        );
+        self.bullet = Some(marker as u8);
+        self
+    }
+
+    /// Sets the number of the first item of a numbered list
+    ///
+    /// Defaults to 1. A list starting at another number can't interrupt a
+    /// paragraph, so it is separated from its title by a blank line.
+    /// Has no effect on bulleted lists.
+    ///
+    /// # Arguments
+    /// * `start` - Number of the first item (0-999999999)
+    pub fn start(mut self, start: usize) -> Self {
+        assert!(
+            start <= 999_999_999,
+            "List start must be range 0-999999999."
+        );
+        self.start = start;
+        self
+    }
+
+    /// Sets how many spaces each nesting level of the list is indented by
+    ///
+    /// Defaults to [RenderOptions::indent](struct.RenderOptions.html#method.indent).
+    /// A list nested in an item is indented relative to the item's marker,
+    /// by at least the marker's width so it stays inside the item. Other
+    /// lists are never indented by more than 3 spaces, so that they aren't
+    /// mistaken for an indented code block. Continuation lines of an item are
+    /// always aligned with its content.
+    ///
+    /// # Arguments
+    /// * `spaces` - Indentation width (1-4)
+    pub fn indent(mut self, spaces: usize) -> Self {
+        assert!(spaces > 0 && spaces <= 4, "List indent must be range 1-4.");
+        self.indent = Some(spaces);
+        self
+    }
+
+    /// Append an item to the list title
+    pub fn title<T: 'a + MarkdownWritable>(mut self, item: T) -> Self {
+        self.title.push(item.into_child());
+        self
+    }
+
+    /// Adds an item to the list
+    ///
+    /// Owned values such as `String` are moved into the list, so they don't
+    /// need to outlive it
+    pub fn item<T: 'a + MarkdownWritable>(mut self, item: T) -> Self {
+        self.items.push((None, item.into_child()));
+        self
+    }
+
+    /// Adds all items yielded by `items` to the list
+    pub fn items_all<T, I>(mut self, items: I) -> Self
+    where
+        T: 'a + MarkdownWritable,
+        I: IntoIterator<Item = T>,
+    {
+        self.items
+            .extend(items.into_iter().map(|item| (None, item.into_child())));
+        self
+    }
+
+    /// Adds a task item (checkbox) to the list
+    ///
+    /// # Arguments
+    /// * `checked` - `true` for a checked `[x]` box, `false` for an empty `[ ]` box
+    /// * `item` - Content written after the checkbox
+    #[cfg(feature = "gfm")]
+    pub fn task_item<T: 'a + MarkdownWritable>(mut self, checked: bool, item: T) -> Self {
+        self.items.push((Some(checked), item.into_child()));
+        self
+    }
+}
+
+impl MarkdownWritable for List<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let line_prefix = ctx.line_prefix();
+        let options = ctx.options();
+        for (it, bracket) in with_next_bracket(&self.title, false) {
+            it.write_to(&mut ctx.child().with_before_bracket(bracket))?;
+        }
+        let indent = self.indent.unwrap_or(options.indent);
+        let prefix = match ctx.list_item {
+            // Nested markers must not be left of the parent item's content
+            Some((marker_prefix, marker_width)) => {
+                let mut prefix = marker_prefix.to_vec();
+                prefix.resize(prefix.len() + indent.max(marker_width), b' ');
+                prefix
+            }
+            None => {
+                let mut prefix = line_prefix.unwrap_or_default().to_vec();
+                prefix.resize(prefix.len() + indent.min(3), b' ');
+                prefix
+            }
+        };
+
+        if self.is_empty() {
+            return Ok(());
+        }
+        if self.numbered && self.start != 1 && !self.title.is_empty() {
+            write_line_prefixed(ctx, b"\n", line_prefix)?;
+        }
+        for (i, (task, it)) in self.items.iter().enumerate() {
+            write_line_prefixed(ctx, b"\n", Some(&prefix))?;
+            let marker_width = if self.numbered {
+                let marker = format!("{}. ", self.start + i);
+                ctx.write_all(marker.as_bytes())?;
+                marker.len()
+            } else {
+                ctx.write_all(&[self.bullet.unwrap_or(options.bullet), b' '])?;
+                2
+            };
+            // Continuation lines are indented to the item content, past the marker
+            let mut item_prefix = prefix.clone();
+            item_prefix.resize(prefix.len() + marker_width, b' ');
+            ctx.start_line();
+            match task {
+                Some(true) => ctx.write_all(b"[x] ")?,
+                Some(false) => ctx.write_all(b"[ ] ")?,
+                None => {}
+            }
+
+            it.write_to(
+                &mut ctx
+                    .child()
+                    .with_line_prefix(Some(&item_prefix))
+                    .with_list_item(&prefix, marker_width),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn count_max_streak(&self, char: u8, _carry: usize) -> (usize, usize) {
+        let mut count = 0;
+        for (_, child) in &self.items {
+            let (c, _) = child.count_max_streak(char, 0);
+            if c > count {
+                count = c;
+            }
+        }
+        (count, 0)
+    }
+
+    fn is_block(&self) -> bool {
+        true
+    }
+
+    fn is_empty_block(&self) -> bool {
+        self.is_empty() && self.title.is_empty()
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_list(self);
+        for child in &self.title {
+            child.accept(visitor);
+        }
+        for (_, item) in &self.items {
+            item.accept(visitor);
+        }
+    }
+}
+
+impl<'a> AsMarkdown<'a> for List<'a> {
+    fn paragraph(self) -> Paragraph<'a> {
+        Paragraph::new().append(self)
+    }
+
+    fn heading(self, level: usize) -> Heading<'a> {
+        self.try_heading(level).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_heading(self, _level: usize) -> Result<Heading<'a>, ValidationError> {
+        Err(ValidationError::Conversion(
+            "Cannot make a Heading from List",
+        ))
+    }
+
+    fn link_to(self, address: &'a str) -> Link<'a> {
+        self.try_link_to(address)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_link_to(self, _address: &'a str) -> Result<Link<'a>, ValidationError> {
+        Err(ValidationError::Conversion("Cannot make a Link from List"))
+    }
+
+    fn bold(self) -> RichText<'a> {
+        self.try_bold().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_bold(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion("Cannot make a List bold"))
+    }
 
-    /// Converts `self` to **bold** [RichText](struct.RichText.html)
-    fn bold(self) -> RichText<'a>;
+    fn italic(self) -> RichText<'a> {
+        self.try_italic().unwrap_or_else(|e| panic!("{}", e))
+    }
 
-    /// Converts `self` to *italic* [RichText](struct.RichText.html)
-    fn italic(self) -> RichText<'a>;
+    fn try_italic(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion("Cannot make a List italic"))
+    }
 
-    /// Converts `self` to `code` [RichText](struct.RichText.html)
-    fn code(self) -> RichText<'a>;
+    fn code(self) -> RichText<'a> {
+        self.try_code().unwrap_or_else(|e| panic!("{}", e))
+    }
 
-    /// Converts `self` to [Quote](struct.Quote.html)
-    fn quote(self) -> Quote<'a>;
+    fn try_code(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion("Cannot make a List code"))
+    }
+
+    #[cfg(feature = "pandoc")]
+    fn try_highlight(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion(
+            "Cannot make a List highlighted",
+        ))
+    }
+
+    fn quote(self) -> Quote<'a> {
+        Quote::new().append(self)
+    }
 }
+//endregion
 
-//region Paragraph
-/// Markdown paragraph
-pub struct Paragraph<'a> {
-    children: Vec<Box<dyn 'a + MarkdownWritable>>,
+//region Quote
+/// A quote block
+pub struct Quote<'a> {
+    children: Vec<Child<'a>>,
+    alert: Option<&'static str>,
 }
 
-impl<'a> Paragraph<'a> {
-    /// Creates an empty paragraph
+impl<'a> Quote<'a> {
+    /// Creates an empty quote block
+    ///
+    /// # Example
+    /// ```
+    /// use markdown_gen::markdown::{to_markdown_string, Quote};
+    ///
+    /// let quote = Quote::new().append("a").append("b");
+    /// assert_eq!(to_markdown_string(quote), ">ab\n\n");
+    /// ```
     pub fn new() -> Self {
         Self {
             children: Vec::new(),
+            alert: None,
         }
     }
 
-    /// Appends an element to the paragraph
+    /// Returns the number of elements in the quote block
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Returns `true` if the quote block has no elements
+    ///
+    /// Empty quote blocks aren't written, even when they are alerts.
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Turns the quote into a GitHub alert, e.g. `> [!NOTE]`
+    ///
+    /// Alert lines are prefixed with `> ` as GitHub documents them.
+    #[cfg(feature = "gfm")]
+    pub fn alert(mut self, kind: AlertKind) -> Self {
+        self.alert = Some(kind.marker());
+        self
+    }
+
+    /// Appends an element to the quote block
     pub fn append<T: 'a + MarkdownWritable>(mut self, element: T) -> Self {
-        self.children.push(Box::new(element));
+        self.children.push(element.into_child());
+        self
+    }
+
+    /// Appends all elements yielded by `elements` to the quote block
+    pub fn append_all<T, I>(mut self, elements: I) -> Self
+    where
+        T: 'a + MarkdownWritable,
+        I: IntoIterator<Item = T>,
+    {
+        self.children
+            .extend(elements.into_iter().map(MarkdownWritable::into_child));
         self
     }
 }
 
-impl MarkdownWritable for &'_ Paragraph<'_> {
-    fn write_to(
-        &self,
-        writer: &mut dyn Write,
-        inner: bool,
-        escape: Escaping,
-        line_prefix: Option<&[u8]>,
-    ) -> Result<(), Error> {
-        for child in &self.children {
-            child.write_to(writer, true, escape, line_prefix)?;
+/// Kind of a GitHub alert, see [Quote::alert](struct.Quote.html#method.alert)
+#[cfg(feature = "gfm")]
+#[derive(Clone, Copy)]
+#[non_exhaustive]
+pub enum AlertKind {
+    /// `[!NOTE]` - useful information
+    Note,
+    /// `[!TIP]` - advice for doing things better
+    Tip,
+    /// `[!IMPORTANT]` - key information
+    Important,
+    /// `[!WARNING]` - urgent information that needs attention
+    Warning,
+    /// `[!CAUTION]` - risks or negative outcomes
+    Caution,
+}
+
+#[cfg(feature = "gfm")]
+impl AlertKind {
+    fn marker(self) -> &'static str {
+        match self {
+            AlertKind::Note => "[!NOTE]",
+            AlertKind::Tip => "[!TIP]",
+            AlertKind::Important => "[!IMPORTANT]",
+            AlertKind::Warning => "[!WARNING]",
+            AlertKind::Caution => "[!CAUTION]",
+        }
+    }
+}
+
+impl Default for Quote<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarkdownWritable for Quote<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        let mut prefix = Vec::new();
+        if let Some(line_prefix) = line_prefix {
+            prefix.extend_from_slice(line_prefix);
+        }
+        let marker: &[u8] = match self.alert {
+            Some(_) => b"> ",
+            None => b">",
+        };
+        prefix.extend_from_slice(marker);
+        if !inner {
+            write_line_prefixed(ctx, b"\n", line_prefix)?;
+        }
+        ctx.write_all(marker)?;
+        if let Some(alert) = self.alert {
+            ctx.write_all(alert.as_bytes())?;
+            write_line_prefixed(ctx, b"\n", Some(&prefix))?;
+        }
+        let mut previous_block = false;
+        let mut blank_lines = 0;
+        for (child, bracket) in with_next_bracket(&self.children, false) {
+            if child.is_blank_line() {
+                blank_lines += 1;
+                continue;
+            }
+            if child.is_empty_block() {
+                continue;
+            }
+            // Without a quoted blank line consecutive blocks would merge
+            if previous_block && child.is_block() {
+                write_line_prefixed(ctx, b"\n\n", Some(&prefix))?;
+            } else if blank_lines > 0 {
+                write_line_prefixed(ctx, b"\n", Some(&prefix))?;
+            }
+            for _ in 0..blank_lines {
+                write_line_prefixed(ctx, b"\n", Some(&prefix))?;
+            }
+            child.write_to(
+                &mut ctx
+                    .child()
+                    .with_line_prefix(Some(&prefix))
+                    .with_before_bracket(bracket),
+            )?;
+            previous_block = child.is_block();
+            blank_lines = 0;
         }
         if !inner {
-            write_line_prefixed(writer, b"\n\n", line_prefix)?;
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
         }
+
         Ok(())
     }
 
-    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
-        let mut carry = carry;
+    fn count_max_streak(&self, char: u8, _carry: usize) -> (usize, usize) {
         let mut count = 0;
         for child in &self.children {
-            let (c, cr) = child.count_max_streak(char, carry);
-            count += c;
-            carry = cr;
+            let (c, _) = child.count_max_streak(char, 0);
+            if c > count {
+                count = c;
+            }
         }
-        count += carry;
         (count, 0)
     }
-}
 
-impl MarkdownWritable for Paragraph<'_> {
-    fn write_to(
-        &self,
-        writer: &mut dyn Write,
-        inner: bool,
-        escape: Escaping,
-        line_prefix: Option<&[u8]>,
-    ) -> Result<(), Error> {
-        (&self).write_to(writer, inner, escape, line_prefix)
+    fn is_block(&self) -> bool {
+        true
     }
 
-    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
-        (&self).count_max_streak(char, carry)
+    fn is_empty_block(&self) -> bool {
+        self.is_empty()
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_quote(self);
+        for child in &self.children {
+            child.accept(visitor);
+        }
     }
 }
 //endregion
 
-//region Heading
-/// Markdown heading
-pub struct Heading<'a> {
-    children: Vec<Box<dyn 'a + MarkdownWritable>>,
-    level: usize,
+//region CodeBlock
+/// Fenced code block
+pub struct CodeBlock<'a> {
+    code: &'a str,
+    language: Option<&'a str>,
 }
 
-impl<'a> Heading<'a> {
-    /// Creates an empty heading
+impl<'a> CodeBlock<'a> {
+    /// Creates a code block containing `code`
     ///
     /// # Arguments
-    /// * `level` - Heading level (1-6)
-    pub fn new(level: usize) -> Self {
-        assert!(level > 0 && level <= 6, "Heading level must be range 1-6.");
+    /// * `code` - Contents of the block, written without escaping
+    pub fn new(code: &'a str) -> Self {
         Self {
-            children: Vec::new(),
-            level,
+            code,
+            language: None,
         }
     }
 
-    /// Appends an element to the heading
-    pub fn append<T: 'a + MarkdownWritable>(mut self, element: T) -> Self {
-        self.children.push(Box::new(element));
+    /// Sets the language written after the opening fence
+    pub fn language(mut self, lang: &'a str) -> Self {
+        self.language = Some(lang);
         self
     }
 }
 
-impl MarkdownWritable for &'_ Heading<'_> {
-    fn write_to(
-        &self,
-        writer: &mut dyn Write,
-        inner: bool,
-        _escape: Escaping,
-        line_prefix: Option<&[u8]>,
-    ) -> Result<(), Error> {
-        assert!(!inner, "Inner headings are forbidden.");
-        let mut prefix = Vec::new();
-        prefix.resize(self.level, b'#');
-        prefix.push(b' ');
-        writer.write_all(&prefix)?;
-        for child in &self.children {
-            child.write_to(writer, true, Normal, line_prefix)?;
+impl MarkdownWritable for CodeBlock<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        let fence = vec![b'`'; backticks_needed(self.code).max(3)];
+
+        ctx.write_all(&fence)?;
+        if let Some(language) = self.language {
+            ctx.write_all(language.as_bytes())?;
+        }
+        // A tab right after `>` would partly count as the space the marker
+        // may be followed by, separate them to keep the tab intact
+        let mut code_prefix = line_prefix.map(<[u8]>::to_vec);
+        if let Some(prefix) = &mut code_prefix {
+            if prefix.ends_with(b">") && self.code.split('\n').any(|l| l.starts_with('\t')) {
+                prefix.push(b' ');
+            }
+        }
+        write_line_prefixed(ctx, b"\n", code_prefix.as_deref())?;
+        write_line_prefixed(ctx, self.code.as_bytes(), code_prefix.as_deref())?;
+        if !self.code.is_empty() && !self.code.ends_with('\n') {
+            write_line_prefixed(ctx, b"\n", line_prefix)?;
+        }
+        ctx.write_all(&fence)?;
+
+        if !inner {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
         }
-        write_line_prefixed(writer, b"\n", line_prefix)?;
         Ok(())
     }
 
-    fn count_max_streak(&self, char: u8, _carry: usize) -> (usize, usize) {
-        let mut carry = 0;
-        let mut count = 0;
-        for child in &self.children {
-            let (c, cr) = child.count_max_streak(char, carry);
-            count += c;
-            carry = cr;
-        }
-        (count, carry)
+    fn count_max_streak(&self, _: u8, _carry: usize) -> (usize, usize) {
+        (0, 0)
+    }
+
+    fn is_block(&self) -> bool {
+        true
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_code_block(self);
     }
 }
 
-impl MarkdownWritable for Heading<'_> {
-    fn write_to(
-        &self,
-        writer: &mut dyn Write,
-        inner: bool,
-        escape: Escaping,
-        line_prefix: Option<&[u8]>,
-    ) -> Result<(), Error> {
-        (&self).write_to(writer, inner, escape, line_prefix)
+impl<'a> AsMarkdown<'a> for CodeBlock<'a> {
+    fn paragraph(self) -> Paragraph<'a> {
+        Paragraph::new().append(self)
     }
 
-    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
-        (&self).count_max_streak(char, carry)
+    fn heading(self, level: usize) -> Heading<'a> {
+        self.try_heading(level).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_heading(self, _level: usize) -> Result<Heading<'a>, ValidationError> {
+        Err(ValidationError::Conversion(
+            "Cannot make a Heading from CodeBlock",
+        ))
+    }
+
+    fn link_to(self, address: &'a str) -> Link<'a> {
+        self.try_link_to(address)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_link_to(self, _address: &'a str) -> Result<Link<'a>, ValidationError> {
+        Err(ValidationError::Conversion(
+            "Cannot make a Link from CodeBlock",
+        ))
+    }
+
+    fn bold(self) -> RichText<'a> {
+        self.try_bold().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_bold(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion("Cannot make a CodeBlock bold"))
+    }
+
+    fn italic(self) -> RichText<'a> {
+        self.try_italic().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_italic(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion(
+            "Cannot make a CodeBlock italic",
+        ))
+    }
+
+    fn code(self) -> RichText<'a> {
+        self.try_code().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_code(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion("Cannot make a CodeBlock code"))
+    }
+
+    #[cfg(feature = "pandoc")]
+    fn try_highlight(self) -> Result<RichText<'a>, ValidationError> {
+        Err(ValidationError::Conversion(
+            "Cannot make a CodeBlock highlighted",
+        ))
+    }
+
+    fn quote(self) -> Quote<'a> {
+        Quote::new().append(self)
     }
 }
 //endregion
 
-//region Table
-/// Markdown Table
-pub struct Table<'a> {
-    gfm: bool,
-    columns: Vec<&'a str>,
-    rows: Vec<Vec<String>>,
+//region HtmlBlock
+/// Raw HTML block written without escaping
+pub struct HtmlBlock<'a> {
+    html: &'a str,
 }
 
-impl<'a> Table<'a> {
-    /// Creates an empty table
-    ///
-    /// # Arguments
-    /// * `gfm` - check to use GitHub Flavored Markdown Spec (supports HTML)
-    /// if not use default spec
-    pub fn new(gfm: bool) -> Self {
-        Self {
-            gfm,
-            columns: vec![],
-            rows: vec![vec![]],
+impl<'a> HtmlBlock<'a> {
+    /// Creates a block containing `html`
+    pub fn new(html: &'a str) -> Self {
+        Self { html }
+    }
+}
+
+impl MarkdownWritable for HtmlBlock<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        write_line_prefixed(
+            ctx,
+            self.html.trim_end_matches('\n').as_bytes(),
+            line_prefix,
+        )?;
+        if !inner {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
         }
+        Ok(())
     }
 
-    /// Add headers to table
-    pub fn header(mut self, columns: Vec<&'a str>) -> Self {
-        self.columns = columns;
-        self
+    fn count_max_streak(&self, _: u8, _carry: usize) -> (usize, usize) {
+        (0, 0)
     }
 
-    /// Appends rows to the table
-    pub fn rows(mut self, rows: Vec<Vec<String>>) -> Self {
-        self.rows = rows;
-        self
+    fn is_block(&self) -> bool {
+        true
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_html_block(self);
     }
 }
+//endregion
 
-impl MarkdownWritable for &'_ Table<'_> {
-    fn write_to(
-        &self,
-        writer: &mut dyn Write,
-        _: bool,
-        _: Escaping,
-        line_prefix: Option<&[u8]>,
-    ) -> Result<(), Error> {
-        // Check if is GitHub Flavored Markdown Spec
-        match self.gfm {
-            true => {
-                let mut table = String::from("<table>");
-                for (k, column) in self.columns.iter().enumerate() {
-                    if k == 0 {
-                        table += format!("<thead><tr><th>{}</th>", column).as_str();
-                    } else {
-                        table += format!("<th>{}</th>", column).as_str();
-                    }
-                }
-                table += "</tr></thead><tbody>";
+//region Comment
+/// HTML comment block, kept in the source but not rendered
+pub struct Comment<'a> {
+    text: &'a str,
+}
 
-                for rows in &self.rows {
-                    for (r, row) in rows.iter().enumerate() {
-                        if r == 0 {
-                            table += format!("<tr><td>{}</td>", row).as_str();
-                        } else {
-                            table += format!("<td>{}</td>", row).as_str();
-                            if (rows.len() - 1) == r {
-                                table += "</tr>";
-                            }
-                        }
-                    }
-                }
-                table += "</tbody></table>";
-                writer.write_all(table.as_ref())?;
-            }
-            false => {
-                // TODO: add the normal spec here
+impl<'a> Comment<'a> {
+    /// Creates a comment containing `text`
+    ///
+    /// The text is written without escaping, except for `-->` and `--!>`,
+    /// which would close the comment early and get a space inserted before `>`.
+    pub fn new(text: &'a str) -> Self {
+        Self { text }
+    }
+}
+
+impl MarkdownWritable for Comment<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        ctx.write_all(b"<!-- ")?;
+        let data = self.text.trim_end_matches('\n').as_bytes();
+        let mut start = 0;
+        for (i, c) in data.iter().enumerate() {
+            if *c == b'>' && (data[..i].ends_with(b"--") || data[..i].ends_with(b"--!")) {
+                write_line_prefixed(ctx, &data[start..i], line_prefix)?;
+                ctx.write_all(b" ")?;
+                start = i;
             }
         }
-
-        write_line_prefixed(writer, b"\n", line_prefix)?;
+        write_line_prefixed(ctx, &data[start..], line_prefix)?;
+        ctx.write_all(b" -->")?;
+        if !inner {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+        }
+        Ok(())
+    }
+
+    fn count_max_streak(&self, _: u8, _carry: usize) -> (usize, usize) {
+        (0, 0)
+    }
+
+    fn is_block(&self) -> bool {
+        true
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_comment(self);
+    }
+}
+//endregion
+
+//region InlineHtml
+/// Raw inline HTML written without escaping
+pub struct InlineHtml<'a> {
+    html: &'a str,
+}
+
+impl<'a> InlineHtml<'a> {
+    /// Creates an inline element containing `html`
+    pub fn new(html: &'a str) -> Self {
+        Self { html }
+    }
+}
+
+impl MarkdownWritable for InlineHtml<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        write_line_prefixed(ctx, self.html.as_bytes(), line_prefix)?;
+        if !inner {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+        }
         Ok(())
     }
 
-    fn count_max_streak(&self, _: u8, _carry: usize) -> (usize, usize) {
-        (0, 0)
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+        self.html.count_max_streak(char, carry)
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_inline_html(self);
     }
 }
+//endregion
 
-impl MarkdownWritable for Table<'_> {
-    fn write_to(
-        &self,
-        writer: &mut dyn Write,
-        inner: bool,
-        escape: Escaping,
-        line_prefix: Option<&[u8]>,
-    ) -> Result<(), Error> {
-        (&self).write_to(writer, inner, escape, line_prefix)
+//region Strikethrough
+/// Struck out text, written as `~~text~~`
+///
+/// Written as `<del>text</del>` where Markdown is not recognized, e.g. in
+/// HTML tables.
+#[cfg(feature = "gfm")]
+pub struct Strikethrough<'a> {
+    text: &'a str,
+}
+
+#[cfg(feature = "gfm")]
+impl<'a> Strikethrough<'a> {
+    /// Creates struck out `text`
+    pub fn new(text: &'a str) -> Self {
+        Self { text }
+    }
+}
+
+#[cfg(feature = "gfm")]
+impl MarkdownWritable for Strikethrough<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        let options = ctx.options();
+        match ctx.escape() {
+            Html => write_html_tagged(ctx, self.text, b"del")?,
+            Normal => {
+                // A tilde inside would end the strikethrough early
+                let mut escape_set = options.escape_set.clone();
+                escape_set.push(b'~');
+                ctx.write_all(b"~~")?;
+                write_escaped(ctx, self.text.as_bytes(), &escape_set, false, line_prefix)?;
+                ctx.write_all(b"~~")?;
+            }
+            _ => {
+                ctx.write_all(b"~~")?;
+                self.text.write_to(&mut ctx.child())?;
+                ctx.write_all(b"~~")?;
+            }
+        }
+        if !inner {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+        }
+        Ok(())
     }
 
     fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
-        (&self).count_max_streak(char, carry)
+        self.text.count_max_streak(char, carry)
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_strikethrough(self);
+        visitor.visit_text(self.text);
     }
 }
 //endregion
 
-//region Link
-/// Markdown link
-pub struct Link<'a> {
-    children: Vec<Box<dyn 'a + MarkdownWritable>>,
-    address: &'a str,
+//region Subscript and Superscript
+/// Subscript text, written as `<sub>text</sub>` or, with the `pandoc` feature, Pandoc's `~text~`
+pub struct Subscript<'a> {
+    text: &'a str,
+    pandoc: bool,
 }
 
-impl<'a> Link<'a> {
-    /// Creates an empty link, which leads to `address`
-    pub fn new(address: &'a str) -> Self {
+impl<'a> Subscript<'a> {
+    /// Creates subscript `text`, written with HTML tags
+    pub fn new(text: &'a str) -> Self {
         Self {
-            children: Vec::new(),
-            address,
+            text,
+            pandoc: false,
         }
     }
 
-    /// Appends an element to the link's text
-    pub fn append<T: 'a + MarkdownWritable>(mut self, element: T) -> Self {
-        self.children.push(Box::new(element));
+    /// Sets whether Pandoc's `~text~` syntax is used instead of HTML tags
+    #[cfg(feature = "pandoc")]
+    pub fn pandoc(mut self, pandoc: bool) -> Self {
+        self.pandoc = pandoc;
         self
     }
 }
 
-impl MarkdownWritable for &'_ Link<'_> {
-    fn write_to(
-        &self,
-        writer: &mut dyn Write,
-        inner: bool,
-        escape: Escaping,
-        line_prefix: Option<&[u8]>,
-    ) -> Result<(), Error> {
-        writer.write_all(b"[")?;
-        for child in &self.children {
-            child.write_to(writer, true, escape, line_prefix)?;
-        }
-        writer.write_all(b"](")?;
-        self.address.write_to(writer, true, escape, line_prefix)?;
-        writer.write_all(b")")?;
-        if !inner {
-            write_line_prefixed(writer, b"\n", line_prefix)?;
-        }
-        Ok(())
+impl MarkdownWritable for Subscript<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        write_script(ctx, self.text, self.pandoc, b"~", b"sub")
     }
 
-    fn count_max_streak(&self, char: u8, _carry: usize) -> (usize, usize) {
-        let (mut addr, addr_cr) = self.address.count_max_streak(char, 0);
-        addr += addr_cr;
-        let mut carry = 0;
-        let mut count = 0;
-        for child in &self.children {
-            let (c, cr) = child.count_max_streak(char, carry);
-            count += c;
-            carry = cr;
-        }
-        count += carry;
-        return if count > addr { (count, 0) } else { (addr, 0) };
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+        self.text.count_max_streak(char, carry)
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_subscript(self);
+        visitor.visit_text(self.text);
     }
 }
 
-impl MarkdownWritable for Link<'_> {
-    fn write_to(
-        &self,
-        writer: &mut dyn Write,
-        inner: bool,
-        escape: Escaping,
-        line_prefix: Option<&[u8]>,
-    ) -> Result<(), Error> {
-        (&self).write_to(writer, inner, escape, line_prefix)
+/// Superscript text, written as `<sup>text</sup>` or, with the `pandoc` feature, Pandoc's `^text^`
+pub struct Superscript<'a> {
+    text: &'a str,
+    pandoc: bool,
+}
+
+impl<'a> Superscript<'a> {
+    /// Creates superscript `text`, written with HTML tags
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            pandoc: false,
+        }
     }
 
-    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
-        (&self).count_max_streak(char, carry)
+    /// Sets whether Pandoc's `^text^` syntax is used instead of HTML tags
+    #[cfg(feature = "pandoc")]
+    pub fn pandoc(mut self, pandoc: bool) -> Self {
+        self.pandoc = pandoc;
+        self
     }
 }
 
-impl<'a> AsMarkdown<'a> for &'a Link<'a> {
-    fn paragraph(self) -> Paragraph<'a> {
-        Paragraph::new().append(self)
+impl MarkdownWritable for Superscript<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        write_script(ctx, self.text, self.pandoc, b"^", b"sup")
     }
 
-    fn heading(self, level: usize) -> Heading<'a> {
-        Heading::new(level).append(self)
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+        self.text.count_max_streak(char, carry)
     }
 
-    fn link_to(self, _address: &'a str) -> Link<'a> {
-        panic!("Link cannot contain another link.");
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_superscript(self);
+        visitor.visit_text(self.text);
     }
+}
 
-    fn bold(self) -> RichText<'a> {
-        panic!("Cannot change link's body. Please use 'x.as_bold().as_link_to(...);'");
+/// Writes `text` between Pandoc `marker`s or inside an HTML `tag`
+///
+/// Pandoc doesn't allow unescaped spaces or markers inside, so they are
+/// escaped with a backslash.
+fn write_script(
+    ctx: &mut RenderContext,
+    text: &str,
+    pandoc: bool,
+    marker: &[u8],
+    tag: &[u8],
+) -> Result<(), Error> {
+    let inner = ctx.is_inner();
+    let escape = ctx.escape();
+    let line_prefix = ctx.line_prefix();
+    let options = ctx.options();
+    if pandoc {
+        ctx.write_all(marker)?;
+        match escape {
+            Normal => {
+                let mut escape_set = options.escape_set.clone();
+                escape_set.extend_from_slice(b" ~^");
+                write_escaped(ctx, text.as_bytes(), &escape_set, false, line_prefix)?;
+            }
+            _ => text.write_to(&mut ctx.child())?,
+        }
+        ctx.write_all(marker)?;
+    } else {
+        write_html_tagged(ctx, text, tag)?;
+    }
+    if !inner {
+        write_line_prefixed(ctx, b"\n\n", line_prefix)?;
     }
+    Ok(())
+}
+//endregion
 
-    fn italic(self) -> RichText<'a> {
-        panic!("Cannot change link's body. Please use 'x.as_italic().as_link_to(...);'");
+//region Kbd
+/// Keyboard key, written as `<kbd>text</kbd>`
+pub struct Kbd<'a> {
+    text: &'a str,
+}
+
+impl<'a> Kbd<'a> {
+    /// Creates a key labeled `text`
+    pub fn new(text: &'a str) -> Self {
+        Self { text }
     }
+}
 
-    fn code(self) -> RichText<'a> {
-        panic!("Cannot change link's body. Please use 'x.as_code().as_link_to(...);'");
+impl MarkdownWritable for Kbd<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        write_html_tagged(ctx, self.text, b"kbd")?;
+        if !inner {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+        }
+        Ok(())
     }
 
-    fn quote(self) -> Quote<'a> {
-        Quote::new().append(self)
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+        self.text.count_max_streak(char, carry)
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_kbd(self);
+        visitor.visit_text(self.text);
     }
 }
 
-impl<'a> AsMarkdown<'a> for Link<'a> {
-    fn paragraph(self) -> Paragraph<'a> {
-        Paragraph::new().append(self)
+/// Writes `text` HTML-escaped inside an inline HTML `tag`
+fn write_html_tagged(ctx: &mut RenderContext, text: &str, tag: &[u8]) -> Result<(), Error> {
+    ctx.write_all(b"<")?;
+    ctx.write_all(tag)?;
+    ctx.write_all(b">")?;
+    text.write_to(&mut ctx.child().with_escape(Html))?;
+    ctx.write_all(b"</")?;
+    ctx.write_all(tag)?;
+    ctx.write_all(b">")
+}
+//endregion
+
+//region Anchor
+/// Empty HTML anchor, `<a id="..."></a>`, which links can lead to
+///
+/// The id is written without Markdown escaping, only characters which would
+/// end the attribute are replaced with HTML entities.
+///
+/// # Example
+/// ```
+/// use markdown_gen::markdown::{to_markdown_string, Anchor, AsMarkdown, Paragraph};
+///
+/// let paragraph = Paragraph::new().append(Anchor("top")).append("Start");
+/// assert_eq!(to_markdown_string(paragraph), "<a id=\"top\"></a>Start\n\n");
+/// let link = "Back to top".link_to("#top");
+/// assert_eq!(to_markdown_string(link), "[Back to top](#top)\n");
+/// ```
+pub struct Anchor<'a>(pub &'a str);
+
+impl MarkdownWritable for Anchor<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        ctx.write_all(b"<a id=\"")?;
+        self.0.write_to(&mut ctx.child().with_escape(Html))?;
+        ctx.write_all(b"\"></a>")?;
+        if !inner {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+        }
+        Ok(())
     }
 
-    fn heading(self, level: usize) -> Heading<'a> {
-        Heading::new(level).append(self)
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+        self.0.count_max_streak(char, carry)
     }
 
-    fn link_to(self, _address: &'a str) -> Link<'a> {
-        panic!("Link cannot contain another link.");
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_anchor(self);
     }
+}
+//endregion
 
-    fn bold(self) -> RichText<'a> {
-        panic!("Cannot change link's body. Please use 'x.as_bold().as_link_to(...);'");
+//region Raw
+/// Markdown written verbatim, without escaping
+///
+/// Useful for splicing in Markdown formatted elsewhere. Unlike
+/// [HtmlBlock](struct.HtmlBlock.html) it is an inline element, lines are still
+/// prefixed when nested in quotes or lists.
+pub struct Raw<'a>(pub &'a str);
+
+impl MarkdownWritable for Raw<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        write_line_prefixed(ctx, self.0.as_bytes(), line_prefix)?;
+        if !inner {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+        }
+        Ok(())
     }
 
-    fn italic(self) -> RichText<'a> {
-        panic!("Cannot change link's body. Please use 'x.as_italic().as_link_to(...);'");
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+        self.0.count_max_streak(char, carry)
     }
 
-    fn code(self) -> RichText<'a> {
-        panic!("Cannot change link's body. Please use 'x.as_code().as_link_to(...);'");
+    fn starts_with_bracket(&self) -> bool {
+        self.0.starts_with('[')
     }
 
-    fn quote(self) -> Quote<'a> {
-        Quote::new().append(self)
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_raw(self);
     }
 }
 //endregion
 
-//region RichText
-/// Text styled with **bold**, *italic* or `code`
-#[derive(Copy, Clone)]
-pub struct RichText<'a> {
-    bold: bool,
-    italic: bool,
-    code: bool,
-    text: &'a str,
+//region Math
+/// Inline math written as `$...$` without Markdown escaping
+///
+/// Unescaped `$` in the body are escaped with a backslash, so they can't
+/// close the span early.
+pub struct Math<'a> {
+    body: &'a str,
 }
 
-impl<'a> RichText<'a> {
-    fn new(text: &'a str) -> Self {
-        Self {
-            bold: false,
-            italic: false,
-            code: false,
-            text,
-        }
+impl<'a> Math<'a> {
+    /// Creates inline math containing the TeX `body`
+    pub fn new(body: &'a str) -> Self {
+        Self { body }
     }
 }
 
-impl MarkdownWritable for &'_ RichText<'_> {
-    fn write_to(
-        &self,
-        writer: &mut dyn Write,
-        inner: bool,
-        mut escape: Escaping,
-        line_prefix: Option<&[u8]>,
-    ) -> Result<(), Error> {
-        let mut symbol = Vec::new();
-        if self.bold {
-            symbol.extend_from_slice(b"**");
-        }
-        if self.italic {
-            symbol.push(b'*');
-        }
-        if self.code {
-            let (mut ticks_needed, carry) = self.text.count_max_streak(b'`', 0);
-            ticks_needed += 1 + carry;
-            symbol.extend(vec![b'`'; ticks_needed]);
-            symbol.push(b' ');
-            escape = InlineCode;
+impl MarkdownWritable for Math<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        ctx.write_all(b"$")?;
+        write_math_escaped(ctx, self.body, line_prefix)?;
+        ctx.write_all(b"$")?;
+        if !inner {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
         }
+        Ok(())
+    }
+
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+        self.body.count_max_streak(char, carry)
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_math(self);
+    }
+}
+
+/// Display math written between `$$` lines without Markdown escaping
+///
+/// Unescaped `$` in the body are escaped with a backslash, so they can't
+/// close the block early.
+pub struct MathBlock<'a> {
+    body: &'a str,
+}
 
-        writer.write_all(&symbol)?;
-        self.text.write_to(writer, true, escape, line_prefix)?;
-        symbol.reverse();
-        writer.write_all(&symbol)?;
+impl<'a> MathBlock<'a> {
+    /// Creates a math block containing the TeX `body`
+    pub fn new(body: &'a str) -> Self {
+        Self { body }
+    }
+}
 
+impl MarkdownWritable for MathBlock<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        ctx.write_all(b"$$")?;
+        write_line_prefixed(ctx, b"\n", line_prefix)?;
+        write_math_escaped(ctx, self.body, line_prefix)?;
+        if !self.body.is_empty() && !self.body.ends_with('\n') {
+            write_line_prefixed(ctx, b"\n", line_prefix)?;
+        }
+        ctx.write_all(b"$$")?;
         if !inner {
-            write_line_prefixed(writer, b"\n\n", line_prefix)?;
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
         }
         Ok(())
     }
 
-    fn count_max_streak(&self, char: u8, _carry: usize) -> (usize, usize) {
-        let (res, cr) = self.text.count_max_streak(char, 0);
-        (res + cr, 0)
+    fn count_max_streak(&self, _: u8, _carry: usize) -> (usize, usize) {
+        (0, 0)
     }
-}
 
-impl MarkdownWritable for RichText<'_> {
-    fn write_to(
-        &self,
-        writer: &mut dyn Write,
-        inner: bool,
-        escape: Escaping,
-        line_prefix: Option<&[u8]>,
-    ) -> Result<(), Error> {
-        (&self).write_to(writer, inner, escape, line_prefix)
+    fn is_block(&self) -> bool {
+        true
     }
 
-    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
-        (&self).count_max_streak(char, carry)
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_math_block(self);
     }
 }
+//endregion
 
-impl<'a> AsMarkdown<'a> for &'a RichText<'a> {
-    fn paragraph(self) -> Paragraph<'a> {
-        Paragraph::new().append(self)
+//region ThematicBreak
+/// Horizontal rule separating blocks
+pub struct ThematicBreak;
+
+impl MarkdownWritable for ThematicBreak {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        // `>---` is only read as a rule thanks to the optional space after `>`
+        if matches!(line_prefix, Some(prefix) if prefix.last() != Some(&b' ')) {
+            ctx.write_all(b" ")?;
+        }
+        ctx.write_all(b"---")?;
+        if !inner {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+        }
+        Ok(())
     }
 
-    fn heading(self, level: usize) -> Heading<'a> {
-        Heading::new(level).append(self)
+    fn count_max_streak(&self, _: u8, _carry: usize) -> (usize, usize) {
+        (0, 0)
     }
 
-    fn link_to(self, address: &'a str) -> Link<'a> {
-        Link::new(address).append(self)
+    fn is_block(&self) -> bool {
+        true
     }
 
-    fn bold(self) -> RichText<'a> {
-        let mut clone = *self;
-        clone.bold = true;
-        *self
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_thematic_break(self);
     }
+}
+//endregion
 
-    fn italic(self) -> RichText<'a> {
-        let mut clone = *self;
-        clone.italic = true;
-        *self
+//region LineBreak
+/// Hard line break inside a paragraph
+pub struct LineBreak;
+
+impl MarkdownWritable for LineBreak {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let line_prefix = ctx.line_prefix();
+        write_line_prefixed(ctx, b"  \n", line_prefix)?;
+        Ok(())
     }
 
-    fn code(self) -> RichText<'a> {
-        let mut clone = *self;
-        clone.code = true;
-        *self
+    fn count_max_streak(&self, _: u8, _carry: usize) -> (usize, usize) {
+        (0, 0)
     }
 
-    fn quote(self) -> Quote<'a> {
-        Quote::new().append(self)
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_line_break(self);
     }
 }
+//endregion
 
-impl<'a> AsMarkdown<'a> for RichText<'a> {
-    fn paragraph(self) -> Paragraph<'a> {
-        Paragraph::new().append(self)
+//region SoftBreak
+/// Soft line break inside a paragraph
+///
+/// Starts a new line in the Markdown source, but is rendered as a space.
+pub struct SoftBreak;
+
+impl MarkdownWritable for SoftBreak {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let line_prefix = ctx.line_prefix();
+        write_line_prefixed(ctx, b"\n", line_prefix)?;
+        Ok(())
     }
 
-    fn heading(self, level: usize) -> Heading<'a> {
-        Heading::new(level).append(self)
+    fn count_max_streak(&self, _: u8, _carry: usize) -> (usize, usize) {
+        (0, 0)
     }
 
-    fn link_to(self, address: &'a str) -> Link<'a> {
-        Link::new(address).append(self)
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_soft_break(self);
     }
+}
+//endregion
 
-    fn bold(mut self) -> RichText<'a> {
-        self.bold = true;
-        self
+//region BlankLine
+/// Additional blank line between two blocks
+///
+/// [Markdown](struct.Markdown.html), [Document](struct.Document.html) and
+/// [Quote](struct.Quote.html) add it to the blank line separating the blocks
+/// around it. Elsewhere it is written as a single line break.
+pub struct BlankLine;
+
+impl MarkdownWritable for BlankLine {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let line_prefix = ctx.line_prefix();
+        write_line_prefixed(ctx, b"\n", line_prefix)?;
+        Ok(())
     }
 
-    fn italic(mut self) -> RichText<'a> {
-        self.italic = true;
-        self
+    fn count_max_streak(&self, _: u8, _carry: usize) -> (usize, usize) {
+        (0, 0)
     }
 
-    fn code(mut self) -> RichText<'a> {
-        self.code = true;
-        self
+    fn is_block(&self) -> bool {
+        true
     }
 
-    fn quote(self) -> Quote<'a> {
-        Quote::new().append(self)
+    fn is_blank_line(&self) -> bool {
+        true
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_blank_line(self);
     }
 }
 //endregion
 
-//region List
-/// Bulleted or numbered list
-pub struct List<'a> {
-    title: Vec<Box<dyn 'a + MarkdownWritable>>,
-    items: Vec<Box<dyn 'a + MarkdownWritable>>,
-    numbered: bool,
+//region Trusted
+/// Writes an element and all of its descendants without escaping
+///
+/// Strings inside are written verbatim, Markdown and HTML they contain is
+/// kept as is. Elements outside the wrapper are still escaped as usual.
+pub struct Trusted<'a> {
+    child: Child<'a>,
 }
 
-impl<'a> List<'a> {
-    /// Creates an empty list
-    /// # Arguments
-    /// * `numbered` - `true` for numbered list, `false` for bulleted list
-    pub fn new(numbered: bool) -> Self {
+impl<'a> Trusted<'a> {
+    /// Creates a wrapper that disables escaping for `element`
+    pub fn new<T: 'a + MarkdownWritable>(element: T) -> Self {
         Self {
-            items: Vec::new(),
-            title: Vec::new(),
-            numbered,
+            child: element.into_child(),
         }
     }
+}
+
+impl MarkdownWritable for Trusted<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        self.child
+            .write_to(&mut ctx.child().with_inner(inner).with_escape(Escaping::None))
+    }
+
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+        self.child.count_max_streak(char, carry)
+    }
+
+    fn is_block(&self) -> bool {
+        self.child.is_block()
+    }
+
+    fn is_blank_line(&self) -> bool {
+        self.child.is_blank_line()
+    }
+
+    fn is_empty_block(&self) -> bool {
+        self.child.is_empty_block()
+    }
+
+    fn starts_with_bracket(&self) -> bool {
+        self.child.starts_with_bracket()
+    }
 
-    /// Append an item to the list title
-    pub fn title<T: 'a + MarkdownWritable>(mut self, item: T) -> Self {
-        self.title.push(Box::new(item));
-        self
+    fn as_heading(&self) -> Option<&Heading<'_>> {
+        self.child.as_heading()
     }
 
-    /// Adds an item to the list
-    pub fn item<T: 'a + MarkdownWritable>(mut self, item: T) -> Self {
-        self.items.push(Box::new(item));
-        self
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_trusted(self);
+        self.child.accept(visitor);
     }
 }
+//endregion
+
+//region Prefixed
+/// Writes an element with `prefix` at the start of each of its lines
+///
+/// The prefix is added after the ones of enclosing quotes and lists, e.g.
+/// four spaces turn the element into an indented code block, whose text
+/// should then be [Trusted](struct.Trusted.html) to skip escaping. It is
+/// written right away for the first line, so the element should start a line.
+pub struct Prefixed<'a> {
+    prefix: &'a str,
+    child: Child<'a>,
+}
 
-impl MarkdownWritable for &'_ List<'_> {
-    fn write_to(
-        &self,
-        writer: &mut dyn Write,
-        _inner: bool,
-        escape: Escaping,
-        line_prefix: Option<&[u8]>,
-    ) -> Result<(), Error> {
-        for it in &self.title {
-            it.write_to(writer, true, escape, line_prefix)?;
+impl<'a> Prefixed<'a> {
+    /// Creates a wrapper prefixing every line of `element` with `prefix`
+    pub fn new<T: 'a + MarkdownWritable>(prefix: &'a str, element: T) -> Self {
+        Self {
+            prefix,
+            child: element.into_child(),
         }
+    }
+}
+
+impl MarkdownWritable for Prefixed<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
         let mut prefix = Vec::new();
-        if line_prefix.is_some() {
-            prefix.extend_from_slice(line_prefix.unwrap());
+        if let Some(line_prefix) = line_prefix {
+            prefix.extend_from_slice(line_prefix);
         }
-        prefix.extend_from_slice(b"   ");
-
-        for it in &self.items {
-            if self.numbered {
-                write_line_prefixed(writer, b"\n1. ", Some(&prefix))?;
-            } else {
-                write_line_prefixed(writer, b"\n* ", Some(&prefix))?;
-            }
-
-            it.write_to(writer, true, escape, Some(&prefix))?;
+        prefix.extend_from_slice(self.prefix.as_bytes());
+        ctx.write_all(self.prefix.as_bytes())?;
+        ctx.start_line();
+        self.child
+            .write_to(&mut ctx.child().with_line_prefix(Some(&prefix)))?;
+        if !inner {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
         }
         Ok(())
     }
 
-    fn count_max_streak(&self, char: u8, _carry: usize) -> (usize, usize) {
-        let mut count = 0;
-        for child in &self.items {
-            let (c, _) = child.count_max_streak(char, 0);
-            if c > count {
-                count = c;
-            }
-        }
-        (count, 0)
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+        self.child.count_max_streak(char, carry)
     }
-}
 
-impl<'a> MarkdownWritable for List<'a> {
-    fn write_to(
-        &self,
-        writer: &mut dyn Write,
-        inner: bool,
-        escape: Escaping,
-        line_prefix: Option<&[u8]>,
-    ) -> Result<(), Error> {
-        (&self).write_to(writer, inner, escape, line_prefix)
+    fn is_block(&self) -> bool {
+        self.child.is_block()
     }
 
-    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
-        (&self).count_max_streak(char, carry)
+    fn is_empty_block(&self) -> bool {
+        self.child.is_empty_block()
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_prefixed(self);
+        self.child.accept(visitor);
     }
 }
+//endregion
 
-impl<'a> AsMarkdown<'a> for List<'a> {
-    fn paragraph(self) -> Paragraph<'a> {
-        Paragraph::new().append(self)
+//region Details
+/// Collapsible section rendered as `<details>` with a `<summary>`
+pub struct Details<'a> {
+    summary: Child<'a>,
+    body: Document<'a>,
+}
+
+impl<'a> Details<'a> {
+    /// Creates a collapsed section with an empty body
+    ///
+    /// # Arguments
+    /// * `summary` - Inline content shown while the section is collapsed
+    pub fn new<T: 'a + MarkdownWritable>(summary: T) -> Self {
+        Self {
+            summary: summary.into_child(),
+            body: Document::new(),
+        }
     }
 
-    fn heading(self, _level: usize) -> Heading<'a> {
-        panic!("Cannot make a Heading from List");
+    /// Appends a block to the section's body
+    pub fn append<T: 'a + MarkdownWritable>(mut self, element: T) -> Self {
+        self.body = self.body.append(element);
+        self
     }
 
-    fn link_to(self, _address: &'a str) -> Link<'a> {
-        panic!("Cannot make a Link from List");
+    /// Appends all blocks yielded by `elements` to the section's body
+    pub fn append_all<T, I>(mut self, elements: I) -> Self
+    where
+        T: 'a + MarkdownWritable,
+        I: IntoIterator<Item = T>,
+    {
+        self.body = self.body.append_all(elements);
+        self
     }
+}
 
-    fn bold(self) -> RichText<'a> {
-        panic!("Cannot make a List bold");
+impl MarkdownWritable for Details<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        ctx.write_all(b"<details><summary>")?;
+        self.summary.write_to(&mut ctx.child())?;
+        ctx.write_all(b"</summary>")?;
+        write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+        self.body.write_to(&mut ctx.child())?;
+        write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+        ctx.write_all(b"</details>")?;
+        if !inner {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+        }
+        Ok(())
     }
 
-    fn italic(self) -> RichText<'a> {
-        panic!("Cannot make a List italic");
+    fn count_max_streak(&self, char: u8, _carry: usize) -> (usize, usize) {
+        let (summary, summary_cr) = self.summary.count_max_streak(char, 0);
+        let (body, _) = self.body.count_max_streak(char, 0);
+        ((summary + summary_cr).max(body), 0)
     }
 
-    fn code(self) -> RichText<'a> {
-        panic!("Cannot make a List code");
+    fn is_block(&self) -> bool {
+        true
     }
 
-    fn quote(self) -> Quote<'a> {
-        Quote::new().append(self)
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_details(self);
+        self.summary.accept(visitor);
+        for child in &self.body.children {
+            child.accept(visitor);
+        }
     }
 }
 //endregion
 
-//region Quote
-/// A quote block
-pub struct Quote<'a> {
-    children: Vec<Box<dyn 'a + MarkdownWritable>>,
+//region Footnote
+/// Footnote definition
+#[cfg(feature = "gfm")]
+pub struct Footnote<'a> {
+    id: &'a str,
+    children: Vec<Child<'a>>,
 }
 
-impl<'a> Quote<'a> {
-    /// Creates an empty quote block
-    fn new() -> Self {
+#[cfg(feature = "gfm")]
+impl<'a> Footnote<'a> {
+    /// Creates an empty footnote definition
+    ///
+    /// # Arguments
+    /// * `id` - Label shared by the definition and its references
+    pub fn new(id: &'a str) -> Self {
         Self {
+            id,
             children: Vec::new(),
         }
     }
 
-    /// Appends an element to the quote block
+    /// Appends an element to the footnote definition
     pub fn append<T: 'a + MarkdownWritable>(mut self, element: T) -> Self {
-        self.children.push(Box::new(element));
+        self.children.push(element.into_child());
         self
     }
+
+    /// Appends all elements yielded by `elements` to the footnote definition
+    pub fn append_all<T, I>(mut self, elements: I) -> Self
+    where
+        T: 'a + MarkdownWritable,
+        I: IntoIterator<Item = T>,
+    {
+        self.children
+            .extend(elements.into_iter().map(MarkdownWritable::into_child));
+        self
+    }
+
+    /// Creates a reference pointing to this footnote
+    pub fn reference(&self) -> FootnoteReference<'a> {
+        FootnoteReference::new(self.id)
+    }
 }
 
-impl MarkdownWritable for &'_ Quote<'_> {
-    fn write_to(
-        &self,
-        writer: &mut dyn Write,
-        inner: bool,
-        escape: Escaping,
-        line_prefix: Option<&[u8]>,
-    ) -> Result<(), Error> {
+#[cfg(feature = "gfm")]
+impl MarkdownWritable for Footnote<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
         let mut prefix = Vec::new();
-        if line_prefix.is_some() {
-            prefix.extend_from_slice(line_prefix.unwrap());
-        }
-        prefix.extend_from_slice(b">");
-        if !inner {
-            write_line_prefixed(writer, b"\n", line_prefix)?;
+        if let Some(line_prefix) = line_prefix {
+            prefix.extend_from_slice(line_prefix);
         }
-        writer.write_all(b">")?;
-        for child in &self.children {
-            child.write_to(writer, true, escape, Some(&prefix))?;
+        prefix.extend_from_slice(b"    ");
+
+        ctx.write_all(b"[^")?;
+        ctx.write_all(self.id.as_bytes())?;
+        ctx.write_all(b"]: ")?;
+        ctx.start_line();
+        for (child, bracket) in with_next_bracket(&self.children, false) {
+            child.write_to(
+                &mut ctx
+                    .child()
+                    .with_line_prefix(Some(&prefix))
+                    .with_before_bracket(bracket),
+            )?;
         }
         if !inner {
-            write_line_prefixed(writer, b"\n\n", line_prefix)?;
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
         }
-
         Ok(())
     }
 
     fn count_max_streak(&self, char: u8, _carry: usize) -> (usize, usize) {
+        let mut carry = 0;
         let mut count = 0;
         for child in &self.children {
-            let (c, _) = child.count_max_streak(char, 0);
-            if c > count {
-                count = c;
-            }
+            let (c, cr) = child.count_max_streak(char, carry);
+            count = count.max(c);
+            carry = cr;
+        }
+        (count.max(carry), 0)
+    }
+
+    fn is_block(&self) -> bool {
+        true
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_footnote(self);
+        for child in &self.children {
+            child.accept(visitor);
         }
-        (count, 0)
     }
 }
-impl<'a> MarkdownWritable for Quote<'a> {
-    fn write_to(
-        &self,
-        writer: &mut dyn Write,
-        inner: bool,
-        escape: Escaping,
-        line_prefix: Option<&[u8]>,
-    ) -> Result<(), Error> {
-        (&self).write_to(writer, inner, escape, line_prefix)
+
+/// Inline reference to a [Footnote](struct.Footnote.html)
+#[cfg(feature = "gfm")]
+pub struct FootnoteReference<'a> {
+    id: &'a str,
+}
+
+#[cfg(feature = "gfm")]
+impl<'a> FootnoteReference<'a> {
+    /// Creates a reference to the footnote labeled `id`
+    pub fn new(id: &'a str) -> Self {
+        Self { id }
     }
+}
 
-    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
-        (&self).count_max_streak(char, carry)
+#[cfg(feature = "gfm")]
+impl MarkdownWritable for FootnoteReference<'_> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let line_prefix = ctx.line_prefix();
+        ctx.write_all(b"[^")?;
+        ctx.write_all(self.id.as_bytes())?;
+        ctx.write_all(b"]")?;
+        if !inner {
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
+        }
+        Ok(())
+    }
+
+    fn count_max_streak(&self, _: u8, _carry: usize) -> (usize, usize) {
+        (0, 0)
+    }
+
+    fn starts_with_bracket(&self) -> bool {
+        true
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_footnote_reference(self);
     }
 }
 //endregion
 
-//region String and &str
+//region String, Cow, &str and char
 impl MarkdownWritable for &str {
-    fn write_to(
-        &self,
-        writer: &mut dyn Write,
-        inner: bool,
-        escape: Escaping,
-        line_prefix: Option<&[u8]>,
-    ) -> Result<(), Error> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let inner = ctx.is_inner();
+        let escape = ctx.escape();
+        let line_prefix = ctx.line_prefix();
         match escape {
             Normal => {
-                write_escaped(writer, self.as_bytes(), b"\\`*_{}[]()#+-.!", line_prefix)?;
+                let escape_set = &ctx.options().escape_set;
+                write_escaped(ctx, self.as_bytes(), escape_set, true, line_prefix)?;
             }
             InlineCode => {
-                writer.write_all(self.as_bytes())?;
+                ctx.write_all(self.as_bytes())?;
+            }
+            Html => {
+                write_html_escaped(ctx, self.as_bytes(), line_prefix)?;
+            }
+            Escaping::None => {
+                write_line_prefixed(ctx, self.as_bytes(), line_prefix)?;
             }
         }
         if !inner {
-            write_line_prefixed(writer, b"\n\n", line_prefix)?;
+            write_line_prefixed(ctx, b"\n\n", line_prefix)?;
         }
         Ok(())
     }
 
     fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
-        let mut iter = self.as_bytes().iter();
+        debug_assert!(char.is_ascii(), "Streak character must be ASCII.");
+        let mut rest = self.as_bytes();
         let mut max = 0;
         let mut current = carry;
         loop {
-            match iter.next() {
-                None => {
-                    break;
-                }
-                Some(ch) => {
-                    if *ch == char {
-                        current += 1;
-                    } else {
-                        if current > max {
-                            max = current;
-                        }
-                        current = 0;
-                    }
-                }
+            let streak = rest.iter().take_while(|ch| **ch == char).count();
+            current += streak;
+            rest = &rest[streak..];
+            if rest.is_empty() {
+                return (max, current);
+            }
+            max = max.max(current);
+            current = 0;
+            // Skip straight to the next streak instead of inspecting every byte
+            match rest.iter().position(|ch| *ch == char) {
+                Some(next) => rest = &rest[next..],
+                None => return (max, 0),
             }
         }
-        (max, current)
+    }
+
+    fn starts_with_bracket(&self) -> bool {
+        self.starts_with('[')
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_text(self);
+    }
+
+    fn into_child<'a>(self) -> Child<'a>
+    where
+        Self: 'a,
+    {
+        Child::Text(self)
+    }
+}
+
+impl MarkdownWritable for char {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        let mut buffer = [0; 4];
+        let text: &str = self.encode_utf8(&mut buffer);
+        text.write_to(ctx)
+    }
+
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+        if *self == char as char {
+            (0, carry + 1)
+        } else {
+            (carry, 0)
+        }
+    }
+
+    fn starts_with_bracket(&self) -> bool {
+        *self == '['
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        visitor.visit_text(self.encode_utf8(&mut [0; 4]))
+    }
+}
+
+impl MarkdownWritable for String {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        self.as_str().write_to(ctx)
+    }
+
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+        self.as_str().count_max_streak(char, carry)
+    }
+
+    fn starts_with_bracket(&self) -> bool {
+        self.starts_with('[')
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        self.as_str().accept(visitor)
+    }
+
+    fn borrowed_child(&self) -> Child<'_> {
+        Child::Text(self.as_str())
     }
 }
 
@@ -904,11 +5395,73 @@ impl<'a> AsMarkdown<'a> for &'a String {
         self.as_str().code()
     }
 
+    #[cfg(feature = "pandoc")]
+    fn try_highlight(self) -> Result<RichText<'a>, ValidationError> {
+        self.as_str().try_highlight()
+    }
+
     fn quote(self) -> Quote<'a> {
         self.as_str().quote()
     }
 }
 
+impl MarkdownWritable for Cow<'_, str> {
+    fn write_to(&self, ctx: &mut RenderContext) -> Result<(), Error> {
+        self.as_ref().write_to(ctx)
+    }
+
+    fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+        self.as_ref().count_max_streak(char, carry)
+    }
+
+    fn starts_with_bracket(&self) -> bool {
+        self.starts_with('[')
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) {
+        self.as_ref().accept(visitor)
+    }
+
+    fn borrowed_child(&self) -> Child<'_> {
+        Child::Text(self.as_ref())
+    }
+}
+
+impl<'a> AsMarkdown<'a> for &'a Cow<'_, str> {
+    fn paragraph(self) -> Paragraph<'a> {
+        self.as_ref().paragraph()
+    }
+
+    fn heading(self, level: usize) -> Heading<'a> {
+        self.as_ref().heading(level)
+    }
+
+    fn link_to(self, address: &'a str) -> Link<'a> {
+        self.as_ref().link_to(address)
+    }
+
+    fn bold(self) -> RichText<'a> {
+        self.as_ref().bold()
+    }
+
+    fn italic(self) -> RichText<'a> {
+        self.as_ref().italic()
+    }
+
+    fn code(self) -> RichText<'a> {
+        self.as_ref().code()
+    }
+
+    #[cfg(feature = "pandoc")]
+    fn try_highlight(self) -> Result<RichText<'a>, ValidationError> {
+        self.as_ref().try_highlight()
+    }
+
+    fn quote(self) -> Quote<'a> {
+        self.as_ref().quote()
+    }
+}
+
 impl<'a> AsMarkdown<'a> for &'a str {
     fn paragraph(self) -> Paragraph<'a> {
         Paragraph::new().append(self)
@@ -934,54 +5487,373 @@ impl<'a> AsMarkdown<'a> for &'a str {
         RichText::new(self).code()
     }
 
+    #[cfg(feature = "pandoc")]
+    fn try_highlight(self) -> Result<RichText<'a>, ValidationError> {
+        RichText::new(self).try_highlight()
+    }
+
     fn quote(self) -> Quote<'a> {
         Quote::new().append(self)
     }
 }
 //endregion
 
-fn write_escaped<W: Write + ?Sized>(
-    writer: &mut W,
-    mut data: &[u8],
+fn write_html_cell(
+    ctx: &mut RenderContext,
+    tag: &str,
+    alignment: Alignment,
+    cell: &dyn MarkdownWritable,
+) -> Result<(), Error> {
+    let escape = match ctx.escape() {
+        Escaping::None => Escaping::None,
+        _ => Html,
+    };
+    let mut rendered = Vec::new();
+    cell.write_to(
+        &mut RenderContext::new(&mut rendered, ctx.options())
+            .with_inner(true)
+            .with_escape(escape),
+    )?;
+    ctx.write_all(format!("<{}{}>", tag, alignment.style()).as_bytes())?;
+    // A blank line would end the HTML block in the middle of the table
+    ctx.write_all(&replace_line_breaks(&rendered, b"<br>"))?;
+    ctx.write_all(format!("</{}>", tag).as_bytes())?;
+    Ok(())
+}
+
+/// Replaces every `\n` or `\r\n` of a rendered table cell with `line_break`
+fn replace_line_breaks(rendered: &[u8], line_break: &[u8]) -> Vec<u8> {
+    let mut replaced = Vec::with_capacity(rendered.len());
+    for (i, c) in rendered.iter().enumerate() {
+        match c {
+            b'\r' if rendered.get(i + 1) == Some(&b'\n') => {}
+            b'\n' => replaced.extend_from_slice(line_break),
+            _ => replaced.push(*c),
+        }
+    }
+    replaced
+}
+
+fn slugify(text: &str) -> String {
+    text.chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Pairs each of the inline `children` with whether the next one starts with `[`
+///
+/// The last child is followed by whatever follows its container, `last`.
+fn with_next_bracket<'c, 'a>(
+    children: &'c [Child<'a>],
+    last: bool,
+) -> impl Iterator<Item = (&'c Child<'a>, bool)> {
+    children.iter().enumerate().map(move |(i, child)| {
+        let next = children.get(i + 1);
+        (child, next.map_or(last, |next| next.starts_with_bracket()))
+    })
+}
+
+/// Writes `data`, escaping bytes from `escape` anywhere
+///
+/// With `line_start`, characters which would start a block as the first
+/// non-space character of a line are escaped there too: `>=~<`, list markers
+/// `+` and `-` followed by a space and the `.` or `)` following an ordered list
+/// number. Whether `data` starts a line, or continues a list number, follows
+/// from what was written to `ctx` before.
+fn write_escaped(
+    ctx: &mut RenderContext,
+    data: &[u8],
     escape: &[u8],
+    line_start: bool,
+    line_prefix: Option<&[u8]>,
+) -> Result<(), Error> {
+    let mut start = 0;
+    let mut line = if line_start {
+        ctx.line_state()
+    } else {
+        LineState::Text
+    };
+    for (i, c) in data.iter().enumerate() {
+        // The end of `data` counts as a space, the next element may start with one
+        let marker_end = matches!(data.get(i + 1), None | Some(b' ' | b'\t' | b'\n'));
+        let at_line_start = line_start && line == LineState::Start;
+        let block_start = match c {
+            b'>' | b'=' | b'~' | b'<' => at_line_start,
+            b'+' => at_line_start && marker_end,
+            // `---` is a thematic break or a setext underline
+            b'-' => at_line_start && (marker_end || data.get(i + 1) == Some(&b'-')),
+            b'.' | b')' => line_start && matches!(line, LineState::Digits(1..=9)) && marker_end,
+            _ => false,
+        };
+        // Text runs on into the next element, which may start with `[`
+        let image_start = *c == b'!'
+            && match data.get(i + 1) {
+                Some(next) => *next == b'[',
+                None => line_start && ctx.before_bracket(),
+            };
+        // `&amp;` and `&#38;` would be read as references to the character
+        let entity_start = *c == b'&' && may_start_entity(&data[i + 1..]);
+        if escape.contains(c) || block_start || image_start || entity_start {
+            write_line_prefixed(ctx, &data[start..i], line_prefix)?;
+            ctx.write_all(b"\\")?;
+            start = i;
+            // The backslash ends any block start on this line
+            line = LineState::Text;
+        } else {
+            line = line.next(*c);
+        }
+    }
+    write_line_prefixed(ctx, &data[start..], line_prefix)
+}
+
+/// Returns `true` if `&` followed by `rest` may be read as an entity or a
+/// numeric character reference, including when the next element could
+/// complete it
+fn may_start_entity(rest: &[u8]) -> bool {
+    let name = match rest.first() {
+        Some(b'#') => &rest[1..],
+        _ => rest,
+    };
+    let length = name
+        .iter()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .count();
+    match name.get(length) {
+        Some(b';') => length > 0,
+        Some(_) => false,
+        None => true,
+    }
+}
+
+/// Writes a link destination
+///
+/// Addresses containing spaces, control characters or parentheses are wrapped
+/// in `<...>`, where they are taken verbatim, instead of being escaped.
+fn write_link_destination(ctx: &mut RenderContext, address: &str) -> Result<(), Error> {
+    match ctx.escape() {
+        Normal => {
+            let line_prefix = ctx.line_prefix();
+            if address
+                .bytes()
+                .any(|c| c <= b' ' || c == 0x7f || c == b'(' || c == b')')
+            {
+                ctx.write_all(b"<")?;
+                write_escaped(ctx, address.as_bytes(), b"\\<>", false, line_prefix)?;
+                ctx.write_all(b">")
+            } else {
+                write_escaped(ctx, address.as_bytes(), b"\\<", false, line_prefix)
+            }
+        }
+        _ => address.write_to(&mut ctx.child()),
+    }
+}
+
+/// Writes ` "title"` with quotes and backslashes in `title` escaped
+fn write_link_title(
+    ctx: &mut RenderContext,
+    title: &str,
+    line_prefix: Option<&[u8]>,
+) -> Result<(), Error> {
+    ctx.write_all(b" \"")?;
+    write_escaped(ctx, title.as_bytes(), b"\\\"", false, line_prefix)?;
+    ctx.write_all(b"\"")?;
+    Ok(())
+}
+
+fn write_html_escaped(
+    ctx: &mut RenderContext,
+    mut data: &[u8],
     line_prefix: Option<&[u8]>,
 ) -> Result<(), Error> {
     loop {
-        let slice_at = data.iter().position(|x| escape.contains(x));
+        let slice_at = data.iter().position(|x| b"&<>\"'".contains(x));
         match slice_at {
             Option::None => {
-                write_line_prefixed(writer, &data, line_prefix)?;
+                write_line_prefixed(ctx, data, line_prefix)?;
                 return Ok(());
             }
             Some(slice_at) => {
-                write_line_prefixed(writer, &data[..slice_at], line_prefix)?;
-                writer.write_all(b"\\")?;
-                write_line_prefixed(writer, &data[slice_at..slice_at + 1], line_prefix)?;
+                write_line_prefixed(ctx, &data[..slice_at], line_prefix)?;
+                let entity: &[u8] = match data[slice_at] {
+                    b'&' => b"&amp;",
+                    b'<' => b"&lt;",
+                    b'>' => b"&gt;",
+                    b'"' => b"&quot;",
+                    _ => b"&#39;",
+                };
+                ctx.write_all(entity)?;
                 data = &data[slice_at + 1..];
             }
         }
     }
 }
 
-fn write_line_prefixed<W: Write + ?Sized>(
-    writer: &mut W,
+/// Renders an inline table cell, escaping pipes and line breaks
+///
+/// A line break would end the row, so it is written as the `&#10;` character
+/// reference instead.
+fn render_table_cell(
+    cell: &dyn MarkdownWritable,
+    escape: Escaping,
+    options: &RenderOptions,
+) -> Result<Vec<u8>, Error> {
+    let mut rendered = Vec::new();
+    cell.write_to(
+        &mut RenderContext::new(&mut rendered, options)
+            .with_inner(true)
+            .with_escape(escape),
+    )?;
+    // Pipes would end the cell, even inside code spans
+    let rendered = replace_line_breaks(&rendered, b"&#10;");
+    let mut escaped = Vec::with_capacity(rendered.len());
+    let mut backslashes = 0;
+    for c in rendered {
+        if c == b'|' && backslashes % 2 == 0 {
+            escaped.push(b'\\');
+        }
+        backslashes = if c == b'\\' { backslashes + 1 } else { 0 };
+        escaped.push(c);
+    }
+    Ok(escaped)
+}
+
+/// Counts the characters of UTF-8 encoded `text`
+fn text_width(text: &[u8]) -> usize {
+    // Only the first byte of each UTF-8 character takes up a column
+    text.iter().filter(|c| **c & 0xC0 != 0x80).count()
+}
+
+/// Replaces spaces in rendered inline Markdown with newlines so lines don't
+/// exceed `width` characters
+///
+/// Spaces inside code spans and links are kept, as are spaces which would
+/// start a line with a character that changes how it is parsed.
+fn wrap_lines(text: &mut [u8], width: usize) {
+    let mut column = 0;
+    let mut last_break: Option<(usize, usize)> = None;
+    let mut code = 0;
+    let mut link_depth = 0usize;
+    // Inside a `<...>` link destination, which may contain spaces and `)`
+    let mut angle_destination = false;
+    let mut i = 0;
+    while i < text.len() {
+        let c = text[i];
+        if c == b'`' && !angle_destination {
+            let run = text[i..].iter().take_while(|x| **x == b'`').count();
+            if code == 0 {
+                code = run;
+            } else if code == run {
+                code = 0;
+            }
+            column += run;
+            i += run;
+            continue;
+        }
+        if c == b'\n' {
+            column = 0;
+            last_break = None;
+            i += 1;
+            continue;
+        }
+        if code == 0 {
+            match c {
+                b'\\' if i + 1 < text.len() => {
+                    column += 1;
+                    i += 1;
+                }
+                _ if angle_destination => angle_destination = c != b'>',
+                b'<' if link_depth > 0 && text[..i].ends_with(b"](") => angle_destination = true,
+                b'[' => link_depth += 1,
+                b']' if text.get(i + 1) != Some(&b'(') => link_depth = link_depth.saturating_sub(1),
+                b')' => link_depth = link_depth.saturating_sub(1),
+                b' ' if link_depth == 0 && can_break_at(text, i) => {
+                    if column > width {
+                        if let Some((at, at_column)) = last_break {
+                            text[at] = b'\n';
+                            column -= at_column + 1;
+                        }
+                    }
+                    last_break = Some((i, column));
+                }
+                _ => {}
+            }
+        }
+        // Only the first byte of each UTF-8 character takes up a column
+        if text[i] & 0xC0 != 0x80 {
+            column += 1;
+        }
+        i += 1;
+    }
+    if column > width {
+        if let Some((at, _)) = last_break {
+            text[at] = b'\n';
+        }
+    }
+}
+
+fn starts_with_list_number(text: &[u8]) -> bool {
+    let digits = text.iter().take_while(|c| c.is_ascii_digit()).count();
+    digits > 0 && matches!(text.get(digits), Some(b'.') | Some(b')'))
+}
+
+fn can_break_at(text: &[u8], at: usize) -> bool {
+    let previous = text[..at].last();
+    let next = &text[at + 1..];
+    previous.is_some_and(|c| *c != b' ')
+        && !next.is_empty()
+        && !matches!(
+            next[0],
+            b' ' | b'\n' | b'>' | b'<' | b'=' | b'|' | b'~' | b'-' | b'+' | b'*' | b'#'
+        )
+        && !starts_with_list_number(next)
+        && !next.starts_with(b"```")
+}
+
+fn write_math_escaped(
+    ctx: &mut RenderContext,
+    body: &str,
+    line_prefix: Option<&[u8]>,
+) -> Result<(), Error> {
+    let bytes = body.as_bytes();
+    let mut start = 0;
+    let mut backslashes = 0;
+    for (i, c) in bytes.iter().enumerate() {
+        if *c == b'$' && backslashes % 2 == 0 {
+            write_line_prefixed(ctx, &bytes[start..i], line_prefix)?;
+            ctx.write_all(b"\\")?;
+            start = i;
+        }
+        backslashes = if *c == b'\\' { backslashes + 1 } else { 0 };
+    }
+    write_line_prefixed(ctx, &bytes[start..], line_prefix)
+}
+
+fn write_line_prefixed(
+    ctx: &mut RenderContext,
     mut data: &[u8],
     line_prefix: Option<&[u8]>,
 ) -> Result<(), Error> {
     match line_prefix {
         None => {
-            writer.write_all(data)?;
+            ctx.write_all(data)?;
         }
         Some(line_prefix) => loop {
             let slice_at = data.iter().position(|x| *x == b'\n');
             match slice_at {
                 Option::None => {
-                    writer.write_all(&data)?;
+                    ctx.write_all(data)?;
                     break;
                 }
                 Some(slice_at) => {
-                    writer.write_all(&data[..slice_at + 1])?;
-                    writer.write_all(line_prefix)?;
+                    ctx.write_all(&data[..slice_at + 1])?;
+                    ctx.write_all(line_prefix)?;
+                    ctx.start_line();
                     data = &data[slice_at + 1..];
                 }
             }
@@ -1,5 +1,538 @@
-use super::Markdown;
-use crate::markdown::{AsMarkdown, List, Table};
+// Extensions are gated behind Cargo features, so the suite should be run
+// for each combination CI builds:
+//   cargo test
+//   cargo test --features gfm
+//   cargo test --features pandoc
+//   cargo test --all-features
+use super::{
+    Escaping, LineEnding, Markdown, MarkdownWritable, RenderContext, RenderOptions, Visitor,
+};
+use crate::markdown::{
+    backticks_needed, to_markdown_string, Alignment, Anchor, AsMarkdown, Autolink, BlankLine,
+    CodeBlock, Comment, CountingWriter, Details, Document, FmtWriter, FrontMatter, Heading,
+    HtmlBlock, Image, InlineHtml, Kbd, LineBreak, Link, List, Math, MathBlock, Paragraph, Prefixed,
+    Quote, Raw, ReferenceTable, RichText, SoftBreak, Span, Subscript, Superscript, Table,
+    TableOfContents, TableRow, ThematicBreak, Trusted, ValidationError,
+};
+#[cfg(feature = "gfm")]
+use crate::markdown::{AlertKind, Footnote, FootnoteReference, Strikethrough};
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, LinkType, Options, Parser, Tag, TagEnd};
+use std::borrow::Cow;
+use std::io::{BufWriter, ErrorKind};
+
+//region FrontMatter
+#[test]
+fn front_matter() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        FrontMatter::new()
+            .field("title", "\"Hello: world\"")
+            .field("tags", "[rust, markdown]"),
+    )
+    .unwrap();
+    md.write("Title".heading(1)).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "---\n\
+        title: \"Hello: world\"\n\
+        tags: [rust, markdown]\n\
+        ---\n\
+        \n\
+        # Title\n"
+    );
+}
+
+#[test]
+fn front_matter_not_first() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("a").unwrap();
+    let error = md.write(FrontMatter::new().field("t", "x")).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    assert_eq!(
+        error
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<ValidationError>()),
+        Some(&ValidationError::FrontMatterNotFirst)
+    );
+    assert_eq!(String::from_utf8(md.into_inner()).unwrap(), "a\n\n");
+
+    let mut md = Markdown::new(Vec::new());
+    let error = md
+        .write(Quote::new().append(FrontMatter::new()))
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+    let document = Document::new()
+        .append("a")
+        .append(FrontMatter::new().field("t", "x"));
+    let error = Markdown::new(Vec::new()).write(document).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+    let document = Document::new()
+        .append(FrontMatter::new().field("t", "x"))
+        .append("a");
+    assert_eq!(to_markdown_string(document), "---\nt: x\n---\n\na\n\n");
+}
+//endregion
+
+//region Streaming
+#[test]
+fn streaming() {
+    let mut md = Markdown::new(Vec::new());
+    md.heading(2, |h| h.text("Title")).unwrap();
+    md.paragraph(|p| {
+        p.text("a.b ")?;
+        p.write("bold".bold())?;
+        p.write(" link".link_to("url"))
+    })
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "## Title\n\na.b **bold**[ link](url)\n\n"
+    );
+
+    let mut md = Markdown::new(Vec::new());
+    md.heading(1, |h| h.text("2. half")).unwrap();
+    md.paragraph(|p| {
+        p.text("2")?;
+        p.text(". half")
+    })
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "# 2. half\n\n2\\. half\n\n"
+    );
+}
+
+#[test]
+fn flush() {
+    let mut md = Markdown::new(BufWriter::with_capacity(1024, Vec::new()));
+    md.write("buffered").unwrap();
+    let (written, buffered) = md.into_inner().into_parts();
+    assert!(written.is_empty());
+    assert_eq!(buffered.unwrap(), b"buffered\n\n");
+
+    let mut md = Markdown::new(BufWriter::with_capacity(1024, Vec::new()));
+    md.write("flushed").unwrap();
+    md.flush().unwrap();
+    let (written, buffered) = md.into_inner().into_parts();
+    assert_eq!(written, b"flushed\n\n");
+    assert!(buffered.unwrap().is_empty());
+}
+
+#[test]
+fn fmt_writer() {
+    let mut text = String::from("existing\n");
+    let mut md = Markdown::new_fmt(&mut text);
+    md.write("Títle".heading(1)).unwrap();
+    md.write("*text*").unwrap();
+    assert_eq!(
+        md.into_inner().into_inner(),
+        "existing\n# Títle\n\n\\*text\\*\n\n"
+    );
+
+    let error = std::io::Write::write_all(&mut FmtWriter::new(String::new()), &[0xC3]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn line_ending_crlf() {
+    let mut md = Markdown::new(Vec::new()).line_ending(LineEnding::Crlf);
+    md.write("Title".heading(1)).unwrap();
+    md.write(Quote::new().append("windows\r\ntext\nmixed"))
+        .unwrap();
+    md.write(List::new(false).item("a").item("b")).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "# Title\r\n\r\n>windows\r\n>text\r\n>mixed\r\n\r\n   * a\r\n   * b"
+    );
+}
+
+#[test]
+fn line_ending_normalized() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(Quote::new().append("one\r\n\r\ntwo\rthree"))
+        .unwrap();
+    md.write(Paragraph::new().append("split\r").append("\nline"))
+        .unwrap();
+    md.write(Paragraph::new().append("lone\r").append("cr"))
+        .unwrap();
+    md.write(Raw("end\r")).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        ">one\n>\n>two\nthree\n\nsplit\nline\n\nlone\ncr\n\nend\n\n"
+    );
+}
+
+#[test]
+fn write_all() {
+    let headings = ["One".heading(1), "Two".heading(2), "Three".heading(2)];
+    let mut md = Markdown::new(Vec::new());
+    md.write("text").unwrap();
+    md.write_all(&headings).unwrap();
+    md.write_all::<Paragraph>(&[]).unwrap();
+    md.write("end").unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "text\n\n# One\n\n## Two\n\n## Three\n\nend\n\n"
+    );
+}
+
+#[test]
+fn write_bom() {
+    let mut md = Markdown::new(Vec::new());
+    md.write_bom().unwrap();
+    md.write_bom().unwrap();
+    md.write("Title".heading(1)).unwrap();
+    md.write_bom().unwrap();
+    md.write("text").unwrap();
+    assert_eq!(md.into_inner(), b"\xEF\xBB\xBF# Title\n\ntext\n\n");
+
+    let mut md = Markdown::new(Vec::new());
+    md.write("Title".heading(1)).unwrap();
+    assert_eq!(md.write_bom().unwrap_err().kind(), ErrorKind::InvalidInput);
+    assert_eq!(md.into_inner(), b"# Title\n");
+}
+
+#[test]
+fn write_raw() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("Usage".heading(1)).unwrap();
+    md.write_raw("\n```sh\n*cargo* build\n```\n").unwrap();
+    md.write("after".paragraph()).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "# Usage\n\n```sh\n*cargo* build\n```\n\nafter\n\n"
+    );
+}
+//endregion
+
+//region RenderOptions
+fn render_with(options: RenderOptions) -> String {
+    let mut md = Markdown::with_options(Vec::new(), options);
+    md.write("Title".heading(1)).unwrap();
+    md.write("first".paragraph()).unwrap();
+    md.write("second".bold().paragraph()).unwrap();
+    md.write("Section".heading(2)).unwrap();
+    md.heading(3, |h| h.text("Streamed")).unwrap();
+    md.write(List::new(false).item("item")).unwrap();
+    String::from_utf8(md.into_inner()).unwrap()
+}
+
+#[test]
+fn render_options_pretty() {
+    assert_eq!(
+        render_with(RenderOptions::new()),
+        "# Title\n\nfirst\n\n**second**\n\n## Section\n\n### Streamed\n\n   * item"
+    );
+}
+
+#[test]
+fn render_options_compact() {
+    assert_eq!(
+        render_with(RenderOptions::new().compact(true)),
+        "# Title\nfirst\n\n**second**\n## Section\n### Streamed\n   * item"
+    );
+}
+
+#[test]
+fn render_options_compact_blocks() {
+    let write = |options: RenderOptions| {
+        let mut md = Markdown::with_options(Vec::new(), options);
+        md.write("text".paragraph()).unwrap();
+        md.write(CodeBlock::new("code")).unwrap();
+        md.write("after code").unwrap();
+        md.write(ThematicBreak).unwrap();
+        md.write("after break").unwrap();
+        md.write(HtmlBlock::new("<div>")).unwrap();
+        md.write("Heading".heading(2)).unwrap();
+        md.write(List::new(false).item("item")).unwrap();
+        md.write("quoted".quote()).unwrap();
+        String::from_utf8(md.into_inner()).unwrap()
+    };
+    let compact = write(RenderOptions::new().compact(true));
+    assert_eq!(
+        compact,
+        "text\n```\ncode\n```\nafter code\n\n---\nafter break\n\n<div>\n\n## Heading\n   * item\n\n>quoted\n"
+    );
+    let pretty = write(RenderOptions::new());
+    assert_eq!(
+        Parser::new(&compact).collect::<Vec<_>>(),
+        Parser::new(&pretty).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn render_options_compact_escaped_hash() {
+    let mut md = Markdown::with_options(Vec::new(), RenderOptions::new().compact(true));
+    md.write("first").unwrap();
+    md.write("#hash").unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "first\n\n\\#hash\n"
+    );
+}
+
+#[test]
+fn render_options_markers() {
+    let options = RenderOptions::new().bullet('-').emphasis('_').indent(2);
+    let mut md = Markdown::with_options(Vec::new(), options);
+    md.write(
+        List::new(false)
+            .item("bold".bold())
+            .item(List::new(false).title("nested").item("italic".italic()))
+            .item(List::new(false).bullet('+').indent(4).item("own")),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "  - __bold__\n  - nested\n    - _italic_\n  - \n      + own"
+    );
+}
+
+#[test]
+fn render_options_element_override() {
+    let mut md = Markdown::with_options(Vec::new(), RenderOptions::new().emphasis('_'));
+    md.write("a".italic().emphasis('*').paragraph().append("b".italic()))
+        .unwrap();
+    assert_eq!(String::from_utf8(md.into_inner()).unwrap(), "*a*_b_\n\n");
+}
+
+#[test]
+#[should_panic(expected = "List bullet must be one of '-', '*' or '+'.")]
+fn render_options_invalid_bullet() {
+    RenderOptions::new().bullet('x');
+}
+
+#[test]
+fn render_options_escape_set() {
+    let options = RenderOptions::new().escape_set(b"\\*|");
+    let mut md = Markdown::with_options(Vec::new(), options);
+    md.write("1.5 - (a|b) *c* _d_".paragraph()).unwrap();
+    md.write("> not a quote".paragraph()).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "1.5 - (a\\|b) \\*c\\* _d_\n\n\\> not a quote\n\n"
+    );
+}
+
+#[test]
+fn render_options_empty_escape_set() {
+    let mut md = Markdown::with_options(Vec::new(), RenderOptions::new().escape_set(b""));
+    md.write("*raw* [text]".link_to("https://x.y")).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "[*raw* [text]](https://x.y)\n"
+    );
+}
+
+#[test]
+#[should_panic(expected = "Escape set must only contain ASCII punctuation.")]
+fn render_options_invalid_escape_set() {
+    RenderOptions::new().escape_set(b"a");
+}
+//endregion
+
+//region Document
+#[test]
+fn document() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        Document::new()
+            .append("Title".heading(1))
+            .append("first paragraph".paragraph())
+            .append(List::new(false).item("a").item("b"))
+            .append("quoted".quote())
+            .append("last"),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "# Title\n\
+        \n\
+        first paragraph\n\
+        \n   \
+        * a\n   \
+        * b\n\
+        \n\
+        >quoted\n\
+        \n\
+        last\n\
+        \n"
+    );
+}
+
+#[cfg(feature = "gfm")]
+#[test]
+fn document_footnotes() {
+    let note = Footnote::new("1").append("The definition.");
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        Document::new()
+            .append("Claim".paragraph().append(note.reference()))
+            .append("Other".paragraph().append(FootnoteReference::new("long")))
+            .footnote(note)
+            .footnote(
+                Footnote::new("long")
+                    .append("First line")
+                    .append(LineBreak)
+                    .append("second line"),
+            ),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "Claim[^1]\n\
+        \n\
+        Other[^long]\n\
+        \n\
+        [^1]: The definition.\n\
+        \n\
+        [^long]: First line  \n    \
+        second line\n\
+        \n"
+    );
+}
+
+#[cfg(feature = "gfm")]
+#[test]
+#[should_panic]
+fn document_duplicate_footnote() {
+    Document::new()
+        .footnote(Footnote::new("1"))
+        .footnote(Footnote::new("1"));
+}
+
+#[test]
+fn document_toc() {
+    let document = Document::new()
+        .heading(1, "Introduction")
+        .append("Some text.")
+        .heading(2, "Setup")
+        .heading(2, "Setup");
+    assert_eq!(
+        to_markdown_string(&document),
+        "# Introduction\n\nSome text.\n\n## Setup\n\n## Setup\n\n"
+    );
+    assert_eq!(
+        to_markdown_string(document.toc()),
+        "   * [Introduction](#introduction)\
+        \n      * [Setup](#setup)\
+        \n      * [Setup](#setup-1)"
+    );
+}
+
+#[test]
+fn document_heading_levels() {
+    let document = Document::new()
+        .append("Title".heading(1))
+        .append("Section".heading(2))
+        .append("text")
+        .append("Subsection".heading(3))
+        .append("Other".heading(1));
+    assert!(document.validate_headings().is_ok());
+
+    let document = Document::new()
+        .append("Title".heading(1))
+        .append("Deep".heading(3))
+        .append("Section".heading(2))
+        .append("Deeper".heading(4));
+    let error = document.validate_headings().unwrap_err();
+    assert_eq!(
+        error,
+        ValidationError::SkippedHeadingLevels(vec![
+            ("Deep".to_string(), 3, 1),
+            ("Deeper".to_string(), 4, 2)
+        ])
+    );
+    assert_eq!(
+        error.to_string(),
+        "Heading levels must not increase by more than one: \"Deep\" (level 3 after 1), \"Deeper\" (level 4 after 2)."
+    );
+}
+
+#[test]
+fn document_stats() {
+    let document = Document::new()
+        .append("Getting started".heading(1))
+        .append(
+            "Read the "
+                .paragraph()
+                .append("manual".bold().link_to("https://e.com/manual"))
+                .append(" or ")
+                .append(Autolink::new("https://e.com")),
+        )
+        .append("Usage".heading(2))
+        .append(
+            List::new(false)
+                .item("one item")
+                .item(Quote::new().append("quoted words here")),
+        )
+        .append(CodeBlock::new("let code = 1;"));
+    let stats = document.stats();
+    assert_eq!(stats.headings, 2);
+    assert_eq!(stats.words, 12);
+    assert_eq!(stats.links, 2);
+}
+
+#[test]
+fn document_in_quote() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        Quote::new().append(
+            Document::new()
+                .append("first".paragraph())
+                .append("second".paragraph()),
+        ),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        ">first\n>\n>second\n\n"
+    );
+}
+
+#[test]
+fn document_macro() {
+    let document = crate::markdown! {
+        heading(1, "Title");
+        paragraph("Some ", "bold".bold(), " text");
+        list!["a", "b"];
+        numbered_list!["one", "two"];
+        quote("quoted");
+        link("home", "https://home");
+        code("rust", "let x = 1;");
+        rule;
+        block(Paragraph::new().append("last"));
+    };
+    assert_eq!(
+        to_markdown_string(document),
+        "# Title\n\
+        \n\
+        Some **bold** text\n\
+        \n   \
+        * a\n   \
+        * b\n\
+        \n   \
+        1. one\n   \
+        2. two\n\
+        \n\
+        >quoted\n\
+        \n\
+        [home](https://home)\n\
+        \n\
+        ```rust\n\
+        let x = 1;\n\
+        ```\n\
+        \n\
+        ---\n\
+        \n\
+        last\n\
+        \n"
+    );
+    assert_eq!(to_markdown_string(crate::markdown! {}), "");
+}
+//endregion
 
 //region Heading
 #[test]
@@ -16,292 +549,2311 @@ fn headings() {
     assert_eq!(
         String::from_utf8(md.into_inner()).unwrap(),
         "# h1\n\
+        \n\
         ## h2\n\
+        \n\
         ### h3\n\
+        \n\
         #### h4\n\
+        \n\
         ##### h5\n\
+        \n\
         ###### h6\n"
     );
 }
 
+#[test]
+fn heading_block_spacing() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("Title".heading(1)).unwrap();
+    md.write("text".paragraph()).unwrap();
+    md.write("Section".heading(2)).unwrap();
+    md.write("plain").unwrap();
+    md.write(List::new(false).item("a")).unwrap();
+    md.write("Last".heading(2)).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "# Title\n\
+        \n\
+        text\n\
+        \n\
+        ## Section\n\
+        \n\
+        plain\n\
+        \n   \
+        * a\n\
+        \n\
+        ## Last\n"
+    );
+}
+
 #[test]
 #[should_panic]
 fn panic_on_inner_heading() {
     let mut md = Markdown::new(Vec::new());
 
-    md.write("h1".paragraph().append("this should panic".heading(1)))
-        .unwrap();
+    md.write("h1".paragraph().append("this should panic".heading(1)))
+        .unwrap();
+}
+
+#[test]
+fn heading_try_new() {
+    assert!(Heading::try_new(3).is_ok());
+    for level in [0, 7].iter() {
+        let error = Heading::try_new(*level).err().unwrap();
+        assert_eq!(error, ValidationError::HeadingLevel(*level));
+    }
+}
+
+#[test]
+fn heading_clamped() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(Heading::clamped(0).append("zero")).unwrap();
+    md.write(Heading::clamped(6).append("six")).unwrap();
+    md.write(Heading::clamped(7).append("seven")).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "# zero\n\n###### six\n\n**seven**\n\n"
+    );
+}
+
+#[cfg(feature = "pandoc")]
+#[test]
+fn heading_clamped_anchor() {
+    let heading = Heading::clamped(7)
+        .append("Deep *one*")
+        .anchor("deep")
+        .setext();
+    assert_eq!(
+        to_markdown_string(heading),
+        "<a id=\"deep\"></a>**Deep \\*one\\***\n\n"
+    );
+}
+
+#[test]
+fn heading_setext() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("Title".heading(1).setext()).unwrap();
+    md.write("Sub".heading(2).append(" é".italic()).setext())
+        .unwrap();
+    md.write("Deep".heading(3).setext()).unwrap();
+    md.write(Quote::new().append(Document::new().append("Quoted".heading(2).setext())))
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "Title\n\
+        =====\n\
+        \n\
+        Sub* é*\n\
+        -------\n\
+        \n\
+        ### Deep\n\
+        \n\
+        >Quoted\n\
+        >------\n\n"
+    );
+}
+
+#[test]
+fn heading_append() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("h1".heading(1).append("appended")).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "# h1appended\n"
+    );
+}
+
+#[cfg(feature = "pandoc")]
+#[test]
+fn heading_anchor() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("Custom Section".heading(2).anchor("custom-id"))
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "## Custom Section {#custom-id}\n"
+    );
+}
+
+#[test]
+fn heading_slug() {
+    assert_eq!(
+        "What's New in v2.0? (Beta)".heading(1).slug(),
+        "whats-new-in-v20-beta"
+    );
+    assert_eq!("Mixed *Case*".heading(2).slug(), "mixed-case");
+    #[cfg(feature = "pandoc")]
+    assert_eq!("Ignored".heading(2).anchor("explicit").slug(), "explicit");
+}
+
+#[test]
+fn table_of_contents() {
+    let headings = [
+        "Getting Started".heading(1),
+        "Install: the *easy* way".heading(2),
+        "Usage".heading(2),
+        "API".bold().heading(1),
+        "Usage".heading(3),
+        Heading::new(2).append("C++ & Rust"),
+    ];
+    let mut md = Markdown::new(Vec::new());
+    md.write(TableOfContents::from_headings(&headings)).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "   * [Getting Started](#getting-started)\
+        \n      * [Install: the \\*easy\\* way](#install-the-easy-way)\
+        \n      * [Usage](#usage)\
+        \n   * [**API**](#api)\
+        \n      * [Usage](#usage-1)\
+        \n      * [C++ & Rust](#c--rust)"
+    );
+}
+//endregion
+
+//region Paragraph
+#[test]
+fn empty_containers() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("A".heading(1)).unwrap();
+    md.write(Paragraph::new()).unwrap();
+    md.write(List::new(false)).unwrap();
+    md.write(Quote::new()).unwrap();
+    md.write("B".heading(1)).unwrap();
+    assert_eq!(String::from_utf8(md.into_inner()).unwrap(), "# A\n\n# B\n");
+
+    let document = Document::new()
+        .append("A".heading(1))
+        .append(Paragraph::new())
+        .append(Quote::new())
+        .append("B".heading(1));
+    assert_eq!(to_markdown_string(document), "# A\n\n# B\n\n");
+
+    let quote = Quote::new()
+        .append("a".paragraph())
+        .append(Paragraph::new())
+        .append("b".paragraph());
+    assert_eq!(to_markdown_string(quote), ">a\n>\n>b\n\n");
+}
+
+#[test]
+fn paragraphs() {
+    let mut md = Markdown::new(Vec::new());
+
+    md.write("test paragraph".paragraph()).unwrap();
+    md.write("test paragraph2".paragraph()).unwrap();
+
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "test paragraph\n\
+        \n\
+        test paragraph2\n\
+        \n"
+    );
+}
+
+#[test]
+fn paragraph_append() {
+    let mut md = Markdown::new(Vec::new());
+
+    md.write("test paragraph".paragraph().append(" append"))
+        .unwrap();
+
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "test paragraph append\n\
+        \n"
+    );
+}
+
+#[test]
+fn paragraph_line_break() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("line one".paragraph().append(LineBreak).append("line two"))
+        .unwrap();
+    md.write(
+        Quote::new()
+            .append("quoted one")
+            .append(LineBreak)
+            .append("quoted two"),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "line one  \nline two\n\n>quoted one  \n>quoted two\n\n"
+    );
+}
+
+#[test]
+fn paragraph_soft_break() {
+    let paragraph = || {
+        "soft"
+            .paragraph()
+            .soft_break()
+            .append("- not a list")
+            .append(LineBreak)
+            .append("hard")
+            .append(SoftBreak)
+            .append("end")
+    };
+    assert_eq!(
+        to_markdown_string(paragraph()),
+        "soft\n\\- not a list  \nhard\nend\n\n"
+    );
+    assert_eq!(
+        to_markdown_string(Quote::new().append(paragraph())),
+        ">soft\n>\\- not a list  \n>hard\n>end\n\n"
+    );
+}
+
+#[test]
+fn paragraph_append_all() {
+    let words = vec!["one ", "two ", "three"];
+    assert_eq!(
+        to_markdown_string(Paragraph::new().append_all(words)),
+        "one two three\n\n"
+    );
+    assert_eq!(
+        to_markdown_string(Document::new().append_all(vec!["a".paragraph(), "b".paragraph()])),
+        "a\n\nb\n\n"
+    );
+}
+
+#[test]
+fn paragraph_append_text() {
+    let x: i32 = 42;
+    let paragraph = Paragraph::new().append("x = ").append_text(x);
+    assert_eq!(to_markdown_string(paragraph), "x = 42\n\n");
+    let paragraph = Paragraph::new().append_text(-1.5).append_text('*');
+    assert_eq!(to_markdown_string(paragraph), "-1.5\\*\n\n");
+}
+
+#[test]
+fn paragraph_wrap() {
+    let text = "Wrapped paragraphs keep diffs small, so every line of this long \
+                paragraph has to fit into forty columns";
+    let paragraph = Paragraph::new()
+        .wrap(40)
+        .append(text)
+        .append(" with ")
+        .append("some long inline code".code())
+        .append(" and ")
+        .append("a link that is not split".link_to("https://example"))
+        .append(" at the very end.");
+    let output = to_markdown_string(&paragraph);
+    assert_eq!(
+        output,
+        "Wrapped paragraphs keep diffs small, so\n\
+        every line of this long paragraph has to\n\
+        fit into forty columns with\n\
+        ` some long inline code ` and\n\
+        [a link that is not split](https://example)\n\
+        at the very end.\n\n"
+    );
+
+    let mut md = Markdown::new(Vec::new());
+    md.write(Quote::new().append(Paragraph::new().wrap(40).append(text)))
+        .unwrap();
+    let output = String::from_utf8(md.into_inner()).unwrap();
+    assert!(output.lines().all(|line| line.chars().count() <= 40));
+    assert!(output.trim().lines().all(|line| line.starts_with('>')));
+
+    let paragraph = Paragraph::new()
+        .wrap(20)
+        .append("aaaa bbbb ")
+        .append("x".link_to("http://e.com/a b) c d e f g h i j k"));
+    assert_eq!(
+        to_markdown_string(&paragraph),
+        "aaaa bbbb\n[x](<http://e.com/a b) c d e f g h i j k>)\n\n"
+    );
+}
+
+#[test]
+fn paragraph_wrap_block_markers() {
+    let paragraph = Paragraph::new()
+        .wrap(10)
+        .append("aaaaaaaa - bbbbbbbb + cccccccc 1. dddddddd 2) eeee");
+    assert_eq!(
+        to_markdown_string(&paragraph),
+        "aaaaaaaa -\nbbbbbbbb +\ncccccccc 1.\ndddddddd 2)\neeee\n\n"
+    );
+}
+
+//endregion
+
+//region Span
+#[test]
+fn span_in_link() {
+    let link = Link::new("https://e.com").append(Span::new().append("bold".bold()).append(" text"));
+    let paragraph = "See ".paragraph().append(link).append(".");
+    assert_eq!(
+        to_markdown_string(paragraph),
+        "See [**bold** text](https://e.com).\n\n"
+    );
+}
+
+#[test]
+fn span_top_level() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(Span::new().append_all(vec!["a", "*b*"])).unwrap();
+    assert_eq!(String::from_utf8(md.into_inner()).unwrap(), "a\\*b\\*\n");
+}
+//endregion
+
+//region String
+#[test]
+fn string() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("test string").unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "test string\n\
+        \n"
+    );
+}
+
+#[test]
+fn char() {
+    let paragraph = Paragraph::new()
+        .append('a')
+        .append('&')
+        .append('*')
+        .append('é');
+    // The next element could complete an entity, like `amp;` does
+    assert_eq!(to_markdown_string(paragraph), "a\\&\\*é\n\n");
+    assert_eq!(
+        to_markdown_string(Paragraph::new().append('&').append("amp;")),
+        "\\&amp;\n\n"
+    );
+
+    let options = RenderOptions::new();
+    let mut html = Vec::new();
+    '&'.write_to(
+        &mut RenderContext::new(&mut html, &options)
+            .with_inner(true)
+            .with_escape(Escaping::Html),
+    )
+    .unwrap();
+    assert_eq!(html, b"&amp;");
+
+    assert_eq!('`'.count_max_streak(b'`', 2), (0, 3));
+    assert_eq!('x'.count_max_streak(b'`', 2), (2, 0));
+    assert_eq!(
+        to_markdown_string(Paragraph::new().append('`').append("`x".code())),
+        "\\``` `x ``\n\n"
+    );
+}
+
+#[test]
+fn string_escaping() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("\\test\\string\\").unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "\\\\test\\\\string\\\\\n\
+        \n"
+    );
+}
+
+#[test]
+fn prose_escaping() {
+    let cases = [
+        (
+            "See section 1.2 (the intro).",
+            "See section 1.2 (the intro).",
+        ),
+        ("a - b + c", "a - b + c"),
+        ("1.5 litres", "1.5 litres"),
+        ("-5 degrees", "-5 degrees"),
+        ("+1", "+1"),
+        ("1234567890. too long", "1234567890. too long"),
+        ("2019. A good year", "2019\\. A good year"),
+    ];
+    for (text, expected) in cases.iter() {
+        assert_eq!(
+            to_markdown_string(text.paragraph()),
+            format!("{}\n\n", expected)
+        );
+    }
+}
+
+#[test]
+fn exclamation_escaping() {
+    assert_eq!(to_markdown_string("Wow!".paragraph()), "Wow!\n\n");
+    assert_eq!(
+        to_markdown_string("not ![x] an image, ! [y]".paragraph()),
+        "not \\!\\[x\\] an image, ! \\[y\\]\n\n"
+    );
+    let options = RenderOptions::new().escape_set(b"!");
+    let mut md = Markdown::with_options(Vec::new(), options);
+    md.write("Wow!").unwrap();
+    assert_eq!(String::from_utf8(md.into_inner()).unwrap(), "Wow\\!\n\n");
+}
+
+#[test]
+fn line_start_escaping() {
+    let cases = [
+        ("1. item", "1\\. item"),
+        ("2) item", "2\\) item"),
+        ("- item", "\\- item"),
+        ("+ item", "\\+ item"),
+        ("* item", "\\* item"),
+        ("# not a heading", "\\# not a heading"),
+        ("> not a quote", "\\> not a quote"),
+        ("  > indented", "  \\> indented"),
+        ("===", "\\==="),
+        ("~~~", "\\~~~"),
+        ("<div>", "\\<div>"),
+        ("a > b = c", "a > b = c"),
+        ("line\n> quote", "line\n\\> quote"),
+        ("line\n12. item", "line\n12\\. item"),
+        ("---", "\\---"),
+        ("1.", "1\\."),
+        ("-", "\\-"),
+    ];
+    for (text, expected) in cases.iter() {
+        let mut output = Vec::new();
+        text.write_to(&mut RenderContext::new(&mut output, &RenderOptions::new()).with_inner(true))
+            .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), *expected);
+    }
+}
+
+#[test]
+fn html_escaping() {
+    let cases = [
+        ("a & b", "a &amp; b"),
+        ("a < b", "a &lt; b"),
+        ("a > b", "a &gt; b"),
+        ("\"a\"", "&quot;a&quot;"),
+        ("it's", "it&#39;s"),
+        ("*not* [markdown]", "*not* [markdown]"),
+    ];
+    for (text, expected) in cases.iter() {
+        let mut output = Vec::new();
+        text.write_to(
+            &mut RenderContext::new(&mut output, &RenderOptions::new())
+                .with_inner(true)
+                .with_escape(Escaping::Html),
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), *expected);
+    }
+}
+
+#[test]
+fn owned_string() {
+    fn build<'a>() -> Paragraph<'a> {
+        let mut paragraph = "Values: ".paragraph();
+        for i in 0..3 {
+            paragraph = paragraph.append(format!("{}.", i));
+        }
+        paragraph
+    }
+
+    let borrowed = String::from("*borrowed*");
+    let mut md = Markdown::new(Vec::new());
+    md.write(build().append(&borrowed)).unwrap();
+    md.write(String::from("owned")).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "Values: 0.1.2.\\*borrowed\\*\n\nowned\n\n"
+    );
+}
+
+#[test]
+fn cow_string() {
+    let text = "*cow* text";
+    let borrowed: Cow<str> = Cow::Borrowed(text);
+    let owned: Cow<str> = Cow::Owned(text.to_string());
+    let expected = to_markdown_string(text);
+    assert_eq!(to_markdown_string(&borrowed), expected);
+    assert_eq!(to_markdown_string(&owned), expected);
+    assert_eq!(to_markdown_string(borrowed.clone()), expected);
+    assert_eq!(to_markdown_string(owned.clone()), expected);
+    assert_eq!(
+        to_markdown_string(Paragraph::new().append(&borrowed).append(owned.bold())),
+        "\\*cow\\* text**\\*cow\\* text**\n\n"
+    );
+}
+//endregion
+
+//region Link
+#[test]
+fn link() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("test link".link_to("https://test.url")).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "[test link](https://test.url)\n"
+    );
+}
+
+#[test]
+fn link_escaping() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("[][]test [] link[][]".link_to("https://test().url()"))
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "[\\[\\]\\[\\]test \\[\\] link\\[\\]\\[\\]](<https://test().url()>)\n"
+    );
+}
+
+#[test]
+fn link_with_text() {
+    let link = Link::with_text("click here".bold(), "http://e.com").title("E");
+    assert_eq!(
+        to_markdown_string(link),
+        "[**click here**](http://e.com \"E\")\n"
+    );
+}
+
+#[test]
+fn link_without_text() {
+    let paragraph = "See "
+        .paragraph()
+        .append(Link::new("http://e.com"))
+        .append(", ")
+        .append(Link::new("docs/read_me.md"))
+        .append(" and ")
+        .append(Link::new("http://e.com/a b").title("spaced"));
+    assert_eq!(
+        to_markdown_string(paragraph),
+        "See <http://e.com>, [docs/read\\_me.md](docs/read_me.md) and \
+        [http://e.com/a b](<http://e.com/a b> \"spaced\")\n\n"
+    );
+    assert_eq!(
+        to_markdown_string(Link::new("http://e.com")),
+        "<http://e.com>\n"
+    );
+}
+
+#[test]
+fn link_destination_spaces() {
+    let link = "spaced".link_to("http://e.com/a (b)");
+    assert_eq!(to_markdown_string(link), "[spaced](<http://e.com/a (b)>)\n");
+    let link = "angled".link_to("http://e.com/<a> b");
+    assert_eq!(
+        to_markdown_string(link),
+        "[angled](<http://e.com/\\<a\\> b>)\n"
+    );
+}
+
+#[test]
+fn link_destination_parentheses() {
+    let link = "Rust".link_to("https://en.wikipedia.org/wiki/Rust_(programming_language)");
+    assert_eq!(
+        to_markdown_string(link),
+        "[Rust](<https://en.wikipedia.org/wiki/Rust_(programming_language)>)\n"
+    );
+    let image = Image::new("https://e.com/a (1).png");
+    assert_eq!(
+        to_markdown_string(image),
+        "![](<https://e.com/a (1).png>)\n\n"
+    );
+}
+
+#[test]
+fn link_append() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        "test link"
+            .link_to("https://test.url")
+            .append(" ")
+            .append("appended"),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "[test link appended](https://test.url)\n"
+    );
+}
+
+#[test]
+fn link_title() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        Paragraph::new()
+            .append(Link::new("http://e.com").append("x").title("hover text"))
+            .append(" ")
+            .append(
+                Link::new("http://e.com")
+                    .append("y")
+                    .title("say \"hi\" \\o/"),
+            ),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "[x](http://e.com \"hover text\") [y](http://e.com \"say \\\"hi\\\" \\\\o/\")\n\n"
+    );
+}
+
+#[test]
+fn reference_link_titles() {
+    let references = ReferenceTable::new();
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        Paragraph::new()
+            .append(references.titled_link("http://e.com", "hover").append("a"))
+            .append(references.titled_link("http://e.com", "hover").append("b"))
+            .append(references.link("http://e.com").append("c")),
+    )
+    .unwrap();
+    md.write(&references).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "[a][1][b][1][c][2]\n\
+        \n\
+        [1]: http://e.com \"hover\"\n\
+        [2]: http://e.com\n\
+        \n"
+    );
+}
+
+#[test]
+fn autolink() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        Paragraph::new()
+            .append(Autolink::new("https://example.com/a_b"))
+            .append(" ")
+            .append(Autolink::new("user@example.com")),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "<https://example.com/a_b> <user@example.com>\n\n"
+    );
+}
+
+#[test]
+fn autolink_invalid() {
+    for target in [
+        "not a url",
+        "x:y",
+        "https://a b",
+        "a@b..c",
+        "<https://x>",
+        "@b.c",
+        "",
+    ]
+    .iter()
+    {
+        let link = Autolink::new(target);
+        assert!(!link.is_valid(), "{} is not valid", target);
+        let mut output = Vec::new();
+        link.write_to(&mut RenderContext::new(&mut output, &RenderOptions::new()).with_inner(true))
+            .unwrap();
+        assert!(!output.starts_with(b"<"));
+    }
+    assert_eq!(
+        to_markdown_string(Paragraph::new().append(Autolink::new("see [x]"))),
+        "see \\[x\\]\n\n"
+    );
+}
+
+#[test]
+fn reference_links() {
+    let references = ReferenceTable::new();
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        "See "
+            .paragraph()
+            .append(references.link("https://rust-lang.org").append("Rust"))
+            .append(", ")
+            .append(references.link("https://crates.io").append("crates"))
+            .append(" and ")
+            .append(references.link("https://rust-lang.org").append("again")),
+    )
+    .unwrap();
+    md.write(&references).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "See [Rust][1], [crates][2] and [again][1]\n\
+        \n\
+        [1]: https://rust-lang.org\n\
+        [2]: https://crates.io\n\
+        \n"
+    );
+}
+//endregion
+
+//region Image
+#[test]
+fn image() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        Image::new("logo.png")
+            .alt("the [logo]")
+            .title("a \"quoted\" title"),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "![the \\[logo\\]](logo.png \"a \\\"quoted\\\" title\")\n\n"
+    );
+}
+
+#[test]
+fn linked_image() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        "see "
+            .paragraph()
+            .append(Image::new("logo.png").alt("logo").link_to("https://e.com")),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "see [![logo](logo.png)](https://e.com)\n\n"
+    );
+}
+//endregion
+
+//region RichText
+#[test]
+fn code() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("co`````de".code()).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "`````` co`````de ``````\n\n"
+    );
+}
+
+#[test]
+fn count_max_streak_multi_byte() {
+    assert_eq!("é``é🎉```x".count_max_streak(b'`', 0), (3, 0));
+    assert_eq!("é`🎉``".count_max_streak(b'`', 0), (1, 2));
+    assert_eq!("``é".count_max_streak(b'`', 1), (3, 0));
+}
+
+#[test]
+fn code_multi_byte() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("é`🎉".code()).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "`` é`🎉 ``\n\n"
+    );
+}
+
+#[test]
+#[should_panic]
+#[cfg(debug_assertions)]
+fn count_max_streak_non_ascii() {
+    "é".count_max_streak(0xA9, 0);
+}
+
+#[test]
+fn code_multi_line() {
+    let mut md = Markdown::new(Vec::new());
+    let error = md.write("line 1\nline 2".code()).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    assert!(md.into_inner().is_empty());
+}
+
+#[test]
+fn bold() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("bo****ld".bold()).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "**bo\\*\\*\\*\\*ld**\n\n"
+    );
+}
+
+#[test]
+fn italic() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("ita**lic".italic()).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "*ita\\*\\*lic*\n\n"
+    );
+}
+
+#[test]
+fn bold_italic() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("bold italic".italic().bold()).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "**_bold italic_**\n\n"
+    );
+
+    let mut md = Markdown::new(Vec::new());
+    md.write("bold italic".bold().italic()).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "**_bold italic_**\n\n"
+    );
+}
+
+#[test]
+fn bold_italic_in_paragraph() {
+    let paragraph = Paragraph::new()
+        .append("a ")
+        .append("b".bold().italic())
+        .append(" c ")
+        .append("d".bold().italic().emphasis('_'))
+        .append(" e");
+    assert_eq!(to_markdown_string(paragraph), "a **_b_** c __*d*__ e\n\n");
+}
+
+#[test]
+fn bold_italic_code() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("bold italic code".italic().bold().code()).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "**_` bold italic code `_**\n\n"
+    );
+
+    let mut md = Markdown::new(Vec::new());
+    md.write("bold italic".bold().italic()).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "**_bold italic_**\n\n"
+    );
+}
+
+#[cfg(feature = "pandoc")]
+#[test]
+fn highlight() {
+    let paragraph = Paragraph::new()
+        .append("important".highlight())
+        .append(" ")
+        .append("both".bold().italic().highlight())
+        .append(" ")
+        .append("x".highlight().code());
+    assert_eq!(
+        to_markdown_string(paragraph),
+        "==important== ==**_both_**== ==` x `==\n\n"
+    );
+}
+
+#[cfg(feature = "pandoc")]
+#[test]
+#[should_panic]
+fn highlight_image() {
+    Image::new("a.png").highlight();
+}
+
+#[test]
+fn as_markdown_without_highlight() {
+    struct Name<'a>(&'a str);
+
+    impl<'a> AsMarkdown<'a> for Name<'a> {
+        fn paragraph(self) -> Paragraph<'a> {
+            self.0.paragraph()
+        }
+
+        fn heading(self, level: usize) -> Heading<'a> {
+            self.0.heading(level)
+        }
+
+        fn link_to(self, address: &'a str) -> Link<'a> {
+            self.0.link_to(address)
+        }
+
+        fn bold(self) -> RichText<'a> {
+            self.0.bold()
+        }
+
+        fn italic(self) -> RichText<'a> {
+            self.0.italic()
+        }
+
+        fn code(self) -> RichText<'a> {
+            self.0.code()
+        }
+
+        fn quote(self) -> Quote<'a> {
+            self.0.quote()
+        }
+    }
+
+    assert_eq!(to_markdown_string(Name("name").bold()), "**name**\n\n");
+    #[cfg(feature = "pandoc")]
+    assert!(Name("name").try_highlight().is_err());
+}
+
+#[test]
+fn underscore_emphasis() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("italic".italic().emphasis('_')).unwrap();
+    md.write("bold".bold().emphasis('_')).unwrap();
+    md.write("both".bold().italic().emphasis('_')).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "_italic_\n\n__bold__\n\n__*both*__\n\n"
+    );
+}
+
+#[test]
+#[should_panic]
+fn invalid_emphasis() {
+    "text".bold().emphasis('~');
+}
+
+#[test]
+fn rich_text_ref_bold() {
+    let rich = "text".italic();
+    let mut md = Markdown::new(Vec::new());
+    md.write((&rich).bold()).unwrap();
+    let output = String::from_utf8(md.into_inner()).unwrap();
+    assert!(output.contains("**"));
+    assert_eq!(output, "**_text_**\n\n");
+}
+
+#[test]
+fn asterisk_escaping() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("test **".bold()).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "**test \\*\\***\n\n"
+    );
+}
+//endregion
+
+//region List
+#[test]
+fn list_with_capacity() {
+    let mut list = List::with_capacity(false, 1000);
+    let buffer = list.items.as_ptr();
+    assert!(list.is_empty());
+    for _ in 0..1000 {
+        list = list.item("item");
+    }
+    assert_eq!(list.len(), 1000);
+    assert_eq!(list.items.capacity(), 1000);
+    assert_eq!(list.items.as_ptr(), buffer);
+}
+
+#[test]
+fn container_len() {
+    let paragraph = Paragraph::with_capacity(2).append("a").append("b".bold());
+    assert_eq!(paragraph.len(), 2);
+    assert!(!paragraph.is_empty());
+    let document = Document::with_capacity(4).append(paragraph);
+    assert_eq!(document.len(), 1);
+    assert!(Document::new().is_empty());
+}
+
+#[test]
+fn list() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        List::new(true).item("item 1").item("bold".bold()).item(
+            List::new(false)
+                .title("nested list")
+                .item("bold".bold().paragraph().append("italic".italic())),
+        ),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "   1. item 1\n   2. **bold**\n   3. nested list\n      * **bold***italic*"
+    );
+}
+
+#[test]
+fn list_items_all() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(List::new(true).items_all((1..=5).map(|n| n.to_string())))
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "   1. 1\n   2. 2\n   3. 3\n   4. 4\n   5. 5"
+    );
+}
+
+#[test]
+fn list_owned_items() {
+    fn build<'a>() -> List<'a> {
+        let names: Vec<String> = (1..=5).map(|n| format!("name {}", n)).collect();
+        let mut list = List::new(false);
+        for name in names {
+            list = list.item(name);
+        }
+        list
+    }
+
+    let mut md = Markdown::new(Vec::new());
+    md.write(build()).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "   * name 1\n   * name 2\n   * name 3\n   * name 4\n   * name 5"
+    );
+}
+
+#[cfg(feature = "gfm")]
+#[test]
+fn task_list() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        List::new(false)
+            .task_item(true, "done")
+            .task_item(false, "todo")
+            .item("plain"),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "   * [x] done\n   * [ ] todo\n   * plain"
+    );
+}
+
+#[test]
+fn list_indent() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        List::new(false).indent(4).item("a").item(
+            List::new(true)
+                .indent(4)
+                .title("b")
+                .item("nested")
+                .item(List::new(false).indent(4).title("c").item("deep")),
+        ),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "   * a\n   * b\n       1. nested\n       2. c\n           * deep"
+    );
+
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        List::new(false)
+            .indent(2)
+            .title("x")
+            .item(List::new(false).indent(2).title("a").item("b")),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "x\n  * a\n    * b"
+    );
+}
+
+#[test]
+fn list_bullet() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        List::new(false)
+            .bullet('-')
+            .item("a")
+            .item(List::new(false).bullet('+').title("b").item("c")),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "   - a\n   - b\n      + c"
+    );
+}
+
+#[test]
+#[should_panic]
+fn list_invalid_bullet() {
+    List::new(false).bullet('#');
+}
+
+#[test]
+fn list_numbering() {
+    let mut list = List::new(true);
+    for _ in 0..11 {
+        list = list.item("item");
+    }
+    list = list.item(List::new(false).title("nested").item("bullet"));
+
+    let mut md = Markdown::new(Vec::new());
+    md.write(list).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "   1. item\
+        \n   2. item\
+        \n   3. item\
+        \n   4. item\
+        \n   5. item\
+        \n   6. item\
+        \n   7. item\
+        \n   8. item\
+        \n   9. item\
+        \n   10. item\
+        \n   11. item\
+        \n   12. nested\
+        \n       * bullet"
+    );
+}
+
+#[test]
+fn list_start() {
+    let list = List::new(true)
+        .start(5)
+        .item("five")
+        .item("six")
+        .item(List::new(false).title("seven").item("nested"));
+    assert_eq!(
+        to_markdown_string(list),
+        "   5. five\
+        \n   6. six\
+        \n   7. seven\
+        \n      * nested"
+    );
+
+    let list = List::new(true)
+        .title("steps")
+        .start(9)
+        .item("nine")
+        .item(List::new(false).title("ten").item("nested"));
+    assert_eq!(
+        to_markdown_string(list),
+        "steps\n\
+        \n   9. nine\
+        \n   10. ten\
+        \n       * nested"
+    );
+}
+
+#[test]
+#[should_panic]
+fn list_invalid_start() {
+    List::new(true).start(1_000_000_000);
+}
+//endregion
+
+//region Table
+#[test]
+fn table() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        Table::new(true)
+            .header(vec!["Header1", "Header2", "Header3"])
+            .rows(vec![vec!["row01".to_string(), "row02".to_string(), "row03".to_string()]]),
+    )
+    .unwrap();
+
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "<table><thead><tr><th>Header1</th><th>Header2</th><th>Header3</th></tr></thead>\
+        <tbody><tr><td>row01</td><td>row02</td><td>row03</td></tr></tbody></table>\n\n"
+    );
+}
+
+#[test]
+fn table_html_escaping() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        Table::new(true)
+            .header(vec!["<b>\"quoted\"</b>"])
+            .rows(vec![vec!["a<b & c".to_string()]]),
+    )
+    .unwrap();
+
+    let output = String::from_utf8(md.into_inner()).unwrap();
+    assert!(output.contains("a&lt;b &amp; c"));
+    assert_eq!(
+        output,
+        "<table><thead><tr><th>&lt;b&gt;&quot;quoted&quot;&lt;/b&gt;</th></tr></thead>\
+        <tbody><tr><td>a&lt;b &amp; c</td></tr></tbody></table>\n\n"
+    );
+}
+
+#[test]
+fn table_commonmark() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        Table::new(false)
+            .header(vec!["Header1", "Header2"])
+            .rows(vec![
+                vec!["row01".to_string(), "row02".to_string()],
+                vec!["row11".to_string(), "row12".to_string()],
+            ]),
+    )
+    .unwrap();
+
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "| Header1 | Header2 |\n\
+        | --- | --- |\n\
+        | row01 | row02 |\n\
+        | row11 | row12 |\n\n"
+    );
+}
+
+#[test]
+fn table_cell_line_breaks() {
+    let rows = vec![vec!["line1\nline2".to_string(), "a\r\n\nb".to_string()]];
+    let table = Table::new(false).header(vec!["A", "B"]).rows(rows.clone());
+    assert_eq!(
+        to_markdown_string(table),
+        "| A | B |\n| --- | --- |\n| line1&#10;line2 | a&#10;&#10;b |\n\n"
+    );
+    let table = Table::new(true).header(vec!["A", "B"]).rows(rows);
+    assert_eq!(
+        to_markdown_string(table),
+        "<table><thead><tr><th>A</th><th>B</th></tr></thead><tbody>\
+        <tr><td>line1<br>line2</td><td>a<br><br>b</td></tr></tbody></table>\n\n"
+    );
+}
+
+#[test]
+fn table_rows_from() {
+    let users: Vec<(i32, &str)> = vec![(1, "Ann"), (2, "Bob_1")];
+    let table = Table::new(false).header(vec!["Id", "Name"]).rows_from(
+        users
+            .iter()
+            .map(|(id, name)| [id as &dyn std::fmt::Display, name]),
+    );
+    assert_eq!(
+        to_markdown_string(table),
+        "| Id | Name |\n| --- | --- |\n| 1 | Ann |\n| 2 | Bob\\_1 |\n\n"
+    );
+}
+
+#[test]
+fn table_outer_pipes() {
+    let table = |outer_pipes| {
+        Table::new(false)
+            .header(vec!["a", "b"])
+            .alignments(vec![Alignment::Left, Alignment::Right])
+            .rows(vec![
+                vec!["1".to_string(), "2".to_string()],
+                vec!["".to_string(), "4".to_string()],
+            ])
+            .outer_pipes(outer_pipes)
+    };
+    assert_eq!(
+        to_markdown_string(table(true)),
+        "| a | b |\n| :--- | ---: |\n| 1 | 2 |\n|  | 4 |\n\n"
+    );
+    assert_eq!(
+        to_markdown_string(table(false)),
+        "a | b\n:--- | ---:\n1 | 2\n|  | 4 |\n\n"
+    );
+
+    let single = Table::new(false)
+        .header(vec!["a"])
+        .rows(vec![vec!["1".to_string()]])
+        .outer_pipes(false);
+    assert_eq!(to_markdown_string(single), "| a |\n| --- |\n| 1 |\n\n");
+}
+
+#[test]
+fn table_pretty() {
+    let table = Table::new(false)
+        .header(vec!["Name", "Qty", "Note"])
+        .alignments(vec![Alignment::Left, Alignment::Right, Alignment::Center])
+        .rows(vec![
+            vec![
+                "Crème brûlée".to_string(),
+                "2".to_string(),
+                "a|b".to_string(),
+            ],
+            vec!["Tea".to_string(), "12".to_string(), "".to_string()],
+        ])
+        .pretty(true);
+    assert_eq!(
+        to_markdown_string(table),
+        "| Name         |  Qty | Note  |\n\
+        | :----------- | ---: | :---: |\n\
+        | Crème brûlée |    2 | a\\|b  |\n\
+        | Tea          |   12 |       |\n\n"
+    );
+
+    let table = Table::new(false)
+        .header(vec!["a", "long header"])
+        .rows(vec![vec!["1".to_string(), "2".to_string()]])
+        .outer_pipes(false)
+        .pretty(true);
+    assert_eq!(
+        to_markdown_string(table),
+        "a   | long header\n--- | -----------\n1   | 2\n\n"
+    );
+}
+
+#[test]
+fn table_commonmark_escaping() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        Table::new(false)
+            .header(vec!["a|b"])
+            .rows(vec![vec!["*x*".to_string()]]),
+    )
+    .unwrap();
+
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "| a\\|b |\n| --- |\n| \\*x\\* |\n\n"
+    );
+}
+
+#[test]
+fn table_rich_cells() {
+    let row = || {
+        TableRow::new()
+            .cell("x".bold())
+            .cell(Link::new("https://e.com").append("a|b"))
+            .cell("a|b".code())
+    };
+    let mut md = Markdown::new(Vec::new());
+    md.write(Table::new(false).header(vec!["a", "b", "c"]).row(row()))
+        .unwrap();
+    md.write(Table::new(true).header(vec!["a", "b", "c"]).row(row()))
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "| a | b | c |\n\
+        | --- | --- | --- |\n\
+        | **x** | [a\\|b](https://e.com) | ` a\\|b ` |\n\
+        \n\
+        <table><thead><tr><th>a</th><th>b</th><th>c</th></tr></thead>\
+        <tbody><tr><td><strong>x</strong></td><td><a href=\"https://e.com\">a|b</a></td>\
+        <td><code>a|b</code></td></tr></tbody></table>\n\n"
+    );
+}
+
+#[test]
+fn parsed_html_table_cells() {
+    let row = TableRow::new()
+        .cell("<b>".italic().bold())
+        .cell("x".link_to("https://e.com/?a&b").title("\"t\""))
+        .cell(Image::new("i.png").alt("a\"lt"))
+        .cell(Autolink::new("me@e.com"));
+    let table = Table::new(true).header(vec!["a"]).row(row);
+    // Everything is HTML, so a parser passes the table through as one block
+    let html = "<table><thead><tr><th>a</th></tr></thead><tbody><tr>\
+        <td><strong><em>&lt;b&gt;</em></strong></td>\
+        <td><a href=\"https://e.com/?a&amp;b\" title=\"&quot;t&quot;\">x</a></td>\
+        <td><img src=\"i.png\" alt=\"a&quot;lt\"></td>\
+        <td><a href=\"mailto:me@e.com\">me@e.com</a></td></tr></tbody></table>\n";
+    assert_renders_as(
+        table,
+        &[
+            Event::Start(Tag::HtmlBlock),
+            Event::Html(CowStr::from(html)),
+            Event::End(TagEnd::HtmlBlock),
+        ],
+    );
+}
+
+#[test]
+fn table_row_mismatch() {
+    let table = Table::new(false).header(vec!["a", "b"]).rows(vec![
+        vec!["1".to_string(), "2".to_string()],
+        vec!["3".to_string()],
+    ]);
+    let error = table.validate().unwrap_err();
+    assert_eq!(
+        error,
+        ValidationError::TableRow {
+            row: 1,
+            cells: 1,
+            columns: 2
+        }
+    );
+    assert_eq!(error.to_string(), "Table row 1 has 1 cells, expected 2.");
+
+    assert_eq!(
+        to_markdown_string(&table),
+        "| a | b |\n| --- | --- |\n| 1 | 2 |\n| 3 |\n\n"
+    );
+
+    let mut md = Markdown::new(Vec::new());
+    let error = md.write(table.strict(true)).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    let error = error
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<ValidationError>());
+    assert!(matches!(
+        error,
+        Some(ValidationError::TableRow { row: 1, .. })
+    ));
+    assert!(md.into_inner().is_empty());
+}
+
+#[test]
+fn table_normalize_rows() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        Table::new(false)
+            .header(vec!["a", "b"])
+            .normalize_rows(true)
+            .rows(vec![
+                vec!["1".to_string()],
+                vec!["2".to_string(), "3".to_string(), "4".to_string()],
+            ]),
+    )
+    .unwrap();
+    md.write(
+        Table::new(true)
+            .header(vec!["a", "b"])
+            .normalize_rows(true)
+            .rows(vec![vec!["1".to_string()]]),
+    )
+    .unwrap();
+
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "| a | b |\n| --- | --- |\n| 1 |  |\n| 2 | 3 |\n\
+        \n\
+        <table><thead><tr><th>a</th><th>b</th></tr></thead>\
+        <tbody><tr><td>1</td><td></td></tr></tbody></table>\n\n"
+    );
+}
+
+#[test]
+fn table_alignments() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        Table::new(false)
+            .header(vec!["a", "b", "c", "d", "e"])
+            .alignments(vec![
+                Alignment::Left,
+                Alignment::Center,
+                Alignment::Right,
+                Alignment::None,
+            ])
+            .rows(vec![]),
+    )
+    .unwrap();
+
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "| a | b | c | d | e |\n| :--- | :---: | ---: | --- | --- |\n\n"
+    );
+}
+
+#[test]
+fn table_alignments_gfm() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        Table::new(true)
+            .header(vec!["a", "b"])
+            .alignments(vec![Alignment::Center])
+            .rows(vec![vec!["1".to_string(), "2".to_string()]]),
+    )
+    .unwrap();
+
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "<table><thead><tr><th style=\"text-align:center\">a</th><th>b</th></tr></thead>\
+        <tbody><tr><td style=\"text-align:center\">1</td><td>2</td></tr></tbody></table>\n\n"
+    );
+}
+
+#[test]
+fn table_without_columns() {
+    let mut md = Markdown::new(Vec::new());
+    let error = md.write(Table::new(false).rows(vec![vec!["a".to_string()]]));
+    assert_eq!(error.unwrap_err().kind(), ErrorKind::InvalidInput);
+    assert!(md.into_inner().is_empty());
+    assert_eq!(
+        to_markdown_string(Table::new(true).rows(vec![vec!["a".to_string()]])),
+        "<table><tbody><tr><td>a</td></tr></tbody></table>\n\n"
+    );
+}
+
+#[test]
+fn table_in_quote() {
+    let table = Table::new(false)
+        .header(vec!["a", "b"])
+        .rows(vec![vec!["1".to_string(), "2".to_string()]]);
+    let doc = Document::new()
+        .append("before")
+        .append(table)
+        .append("after");
+    assert_eq!(
+        to_markdown_string(Quote::new().append(doc)),
+        ">before\n>\n>| a | b |\n>| --- | --- |\n>| 1 | 2 |\n>\n>after\n\n"
+    );
+}
+
+#[test]
+fn table_in_quote_gfm() {
+    let table = Table::new(true)
+        .header(vec!["a"])
+        .row(TableRow::new().cell(Paragraph::new().append("x").append(LineBreak).append("y")));
+    let doc = Document::new().append(table).append("after");
+    assert_eq!(
+        to_markdown_string(Quote::new().append(doc)),
+        "><table><thead><tr><th>a</th></tr></thead><tbody><tr><td>x  <br>y</td></tr></tbody></table>\n>\n>after\n\n"
+    );
+}
+//endregion
+
+//region Quote
+#[test]
+fn quote() {
+    let mut md = Markdown::new(Vec::new());
+    md.write("bold quote".bold().quote()).unwrap();
+    md.write("code quote".code().quote()).unwrap();
+    md.write("test ".quote().append("link".link_to("sample.url")))
+        .unwrap();
+    md.write(
+        List::new(true)
+            .title("quoted list")
+            .item("item")
+            .item(
+                List::new(false)
+                    .title("nested quoted list")
+                    .item("bold item quote".bold().quote())
+                    .item("test".link_to("sample.url")),
+            )
+            .quote(),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        ">**bold quote**\n\n>` code quote `\n\n>test [link](sample.url)\n\n>quoted list\n>   1. item\n>   2. nested quoted list\n>      * >**bold item quote**\n>      * [test](sample.url)\n\n"
+    );
+}
+
+#[cfg(feature = "gfm")]
+#[test]
+fn quote_alert() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        Quote::new()
+            .alert(AlertKind::Warning)
+            .append("first line")
+            .append(LineBreak)
+            .append("second line"),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "> [!WARNING]\n> first line  \n> second line\n\n"
+    );
+}
+
+#[test]
+fn quote_paragraphs() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        Quote::new()
+            .append("first paragraph".paragraph())
+            .append("second paragraph".paragraph()),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        ">first paragraph\n>\n>second paragraph\n\n"
+    );
+}
+
+#[test]
+fn nested_quote() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        Quote::new().append(
+            Quote::new()
+                .append("first")
+                .append(LineBreak)
+                .append("second"),
+        ),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        ">>first  \n>>second\n\n"
+    );
+}
+
+#[test]
+fn nested_quote_three_levels() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        Quote::new().append("outer").append(LineBreak).append(
+            Quote::new().append(
+                Quote::new()
+                    .append("first")
+                    .append(LineBreak)
+                    .append("second"),
+            ),
+        ),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        ">outer  \n>>>first  \n>>>second\n\n"
+    );
+}
+//endregion
+
+//region CodeBlock
+#[test]
+fn code_block() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(CodeBlock::new("fn main() {}\nlet x = *y;").language("rust"))
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "```rust\nfn main() {}\nlet x = *y;\n```\n\n"
+    );
+}
+
+#[test]
+fn code_block_backticks() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(CodeBlock::new("````\ncode\n````\n")).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "`````\n````\ncode\n````\n`````\n\n"
+    );
+}
+
+#[test]
+fn code_block_in_quote() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(CodeBlock::new("line 1\nline 2").quote()).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        ">```\n>line 1\n>line 2\n>```\n\n"
+    );
+}
+
+#[test]
+fn code_block_tabs_in_list() {
+    let code = CodeBlock::new("if a {\n\tb();\n\t\tc();\n}");
+    let list = List::new(false).item("item").item(code);
+    assert_renders_as(
+        list,
+        &[
+            Event::Start(Tag::List(None)),
+            Event::Start(Tag::Item),
+            Event::Text(CowStr::from("item")),
+            Event::End(TagEnd::Item),
+            Event::Start(Tag::Item),
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(CowStr::from("")))),
+            Event::Text(CowStr::from("if a {\n\tb();\n\t\tc();\n}\n")),
+            Event::End(TagEnd::CodeBlock),
+            Event::End(TagEnd::Item),
+            Event::End(TagEnd::List(false)),
+        ],
+    );
+}
+
+#[test]
+fn code_block_tabs_in_quote() {
+    let quote = Quote::new().append(CodeBlock::new("if a {\n\tb();\n}\n"));
+    assert_eq!(
+        to_markdown_string(quote),
+        ">```\n> if a {\n> \tb();\n> }\n> ```\n\n"
+    );
+}
+//endregion
+
+//region HtmlBlock
+#[test]
+fn html_block() {
+    let html = "<div align=\"center\">\n  <b>*raw*</b>\n</div>\n";
+    let mut md = Markdown::new(Vec::new());
+    md.write("before").unwrap();
+    md.write(HtmlBlock::new(html)).unwrap();
+    md.write(List::new(false).item(HtmlBlock::new(html)))
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "before\n\
+        \n\
+        <div align=\"center\">\n  <b>*raw*</b>\n</div>\n\
+        \n   \
+        * <div align=\"center\">\n       <b>*raw*</b>\n     </div>"
+    );
+}
+//endregion
+
+//region Comment
+#[test]
+fn comment() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(Comment::new("generated by *gen*, do not edit"))
+        .unwrap();
+    md.write("text").unwrap();
+    md.write(Quote::new().append(Comment::new("a\nb"))).unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "<!-- generated by *gen*, do not edit -->\n\ntext\n\n><!-- a\n>b -->\n\n"
+    );
+}
+
+#[test]
+fn comment_closing_sequence() {
+    let comment = Comment::new("a --> b --!> c ---> d -- e");
+    assert_eq!(
+        to_markdown_string(comment),
+        "<!-- a -- > b --! > c --- > d -- e -->\n\n"
+    );
+}
+//endregion
+
+//region InlineHtml
+#[test]
+fn inline_html() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        "Press "
+            .paragraph()
+            .append(InlineHtml::new("<kbd>Ctrl</kbd>"))
+            .append(" + C.")
+            .append(InlineHtml::new("<sup>*</sup>")),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(md.into_inner()).unwrap(),
+        "Press <kbd>Ctrl</kbd> + C.<sup>*</sup>\n\n"
+    );
+}
+//endregion
+
+//region Strikethrough
+#[cfg(feature = "gfm")]
+#[test]
+fn strikethrough() {
+    let paragraph = Paragraph::new()
+        .append("was ")
+        .append(Strikethrough::new("~5~ *10*"))
+        .append(" 3");
+    assert_eq!(
+        to_markdown_string(&paragraph),
+        "was ~~\\~5\\~ \\*10\\*~~ 3\n\n"
+    );
+    let expected = paragraph_events(vec![
+        Event::Text(CowStr::from("was ")),
+        Event::Start(Tag::Strikethrough),
+        Event::Text(CowStr::from("~5~ *10*")),
+        Event::End(TagEnd::Strikethrough),
+        Event::Text(CowStr::from(" 3")),
+    ]);
+    assert_parses_as(paragraph, Options::ENABLE_STRIKETHROUGH, &expected);
+}
+
+#[cfg(feature = "gfm")]
+#[test]
+fn strikethrough_in_table() {
+    let table = Table::new(true)
+        .header(vec!["a"])
+        .row(TableRow::new().cell(Strikethrough::new("<x>")));
+    assert_eq!(
+        to_markdown_string(table),
+        "<table><thead><tr><th>a</th></tr></thead><tbody><tr><td><del>&lt;x&gt;</del></td></tr></tbody></table>\n\n"
+    );
+}
+//endregion
+
+//region Subscript and Superscript
+#[test]
+#[cfg(feature = "pandoc")]
+fn subscript_superscript_pandoc() {
+    let paragraph = Paragraph::new()
+        .append("H")
+        .append(Subscript::new("2").pandoc(true))
+        .append("O, x")
+        .append(Superscript::new("a b^c").pandoc(true));
+    assert_eq!(to_markdown_string(paragraph), "H~2~O, x^a\\ b\\^c^\n\n");
+}
+
+#[test]
+fn subscript_superscript_html() {
+    let paragraph = Paragraph::new()
+        .append("H")
+        .append(Subscript::new("2"))
+        .append("O, x")
+        .append(Superscript::new("<n>"));
+    assert_eq!(
+        to_markdown_string(paragraph),
+        "H<sub>2</sub>O, x<sup>&lt;n&gt;</sup>\n\n"
+    );
+}
+//endregion
+
+//region Kbd
+#[test]
+fn kbd() {
+    let paragraph = Paragraph::new()
+        .append("Copy with ")
+        .append(Kbd::new("Ctrl"))
+        .append("+")
+        .append(Kbd::new("C"))
+        .append(".");
+    assert_eq!(
+        to_markdown_string(paragraph),
+        "Copy with <kbd>Ctrl</kbd>+<kbd>C</kbd>.\n\n"
+    );
+}
+
+#[test]
+fn kbd_in_link() {
+    let link = Link::new("https://e.com").append(Kbd::new("<*>"));
+    assert_eq!(
+        to_markdown_string(link),
+        "[<kbd>&lt;*&gt;</kbd>](https://e.com)\n"
+    );
+}
+//endregion
+
+//region Anchor
+#[test]
+fn anchor() {
+    let document = Document::new()
+        .append(Paragraph::new().append(Anchor("top")).append("Intro_text"))
+        .append("Back to top".link_to("#top").paragraph());
+    assert_eq!(
+        to_markdown_string(document),
+        "<a id=\"top\"></a>Intro\\_text\n\n[Back to top](#top)\n\n"
+    );
+}
+
+#[test]
+fn anchor_attribute_escaping() {
+    assert_eq!(
+        to_markdown_string(Anchor("a\"b_c")),
+        "<a id=\"a&quot;b_c\"></a>\n\n"
+    );
+}
+//endregion
+
+//region Raw
+#[test]
+fn raw() {
+    let paragraph = || {
+        "not *escaped*, "
+            .paragraph()
+            .append(Raw("**already bold**"))
+    };
+    assert_eq!(
+        to_markdown_string(paragraph()),
+        "not \\*escaped\\*, **already bold**\n\n"
+    );
+    assert_renders_as(
+        paragraph(),
+        &paragraph_events(vec![
+            Event::Text(CowStr::from("not *escaped*, ")),
+            Event::Start(Tag::Strong),
+            Event::Text(CowStr::from("already bold")),
+            Event::End(TagEnd::Strong),
+        ]),
+    );
+}
+
+#[test]
+fn raw_in_quote() {
+    let quote = Quote::new().append(Raw("*a*\n# b"));
+    assert_eq!(to_markdown_string(quote), ">*a*\n># b\n\n");
 }
+//endregion
 
+//region Prefixed
 #[test]
-fn heading_append() {
+fn prefixed() {
+    let paragraph = "first *line*\nsecond line".paragraph();
     let mut md = Markdown::new(Vec::new());
-    md.write("h1".heading(1).append("appended")).unwrap();
+    md.write("before").unwrap();
+    md.write(Prefixed::new("    ", paragraph)).unwrap();
+    md.write("after").unwrap();
     assert_eq!(
         String::from_utf8(md.into_inner()).unwrap(),
-        "# h1appended\n"
+        "before\n\n    first \\*line\\*\n    second line\n\nafter\n\n"
     );
 }
+
+#[test]
+fn prefixed_in_quote() {
+    let quote = Quote::new().append(Prefixed::new(":: ", "a\nb".paragraph()));
+    assert_eq!(to_markdown_string(quote), ">:: a\n>:: b\n\n");
+}
 //endregion
 
-//region Paragraph
+//region Trusted
 #[test]
-fn paragraphs() {
-    let mut md = Markdown::new(Vec::new());
+fn trusted() {
+    let paragraph = Paragraph::new()
+        .append("*a* ")
+        .append(Trusted::new("*b* ".bold()))
+        .append(Trusted::new("<c>".link_to("d_(e)")))
+        .append(" *f*");
+    assert_eq!(
+        to_markdown_string(paragraph),
+        "\\*a\\* ***b* **[<c>](d_(e)) \\*f\\*\n\n"
+    );
+}
 
-    md.write("test paragraph".paragraph()).unwrap();
-    md.write("test paragraph2".paragraph()).unwrap();
+#[test]
+fn trusted_block() {
+    let document = Document::new()
+        .append(Trusted::new(Quote::new().append("# *a*")))
+        .append("# *b*");
+    assert_eq!(to_markdown_string(document), "># *a*\n\n\\# \\*b\\*\n\n");
+}
+//endregion
 
+//region Math
+#[test]
+fn inline_math() {
+    let mut md = Markdown::new(Vec::new());
+    md.write(
+        "Pythagoras: "
+            .paragraph()
+            .append(Math::new("a^2 + b^2 = c^2"))
+            .append(", cost ")
+            .append(Math::new("5$ \\$")),
+    )
+    .unwrap();
     assert_eq!(
         String::from_utf8(md.into_inner()).unwrap(),
-        "test paragraph\n\
-        \n\
-        test paragraph2\n\
-        \n"
+        "Pythagoras: $a^2 + b^2 = c^2$, cost $5\\$ \\$$\n\n"
     );
 }
 
 #[test]
-fn paragraph_append() {
+fn math_block() {
     let mut md = Markdown::new(Vec::new());
-
-    md.write("test paragraph".paragraph().append(" append"))
+    md.write(MathBlock::new("\\int_0^1 x\\,dx")).unwrap();
+    md.write(Quote::new().append(MathBlock::new("a\nb")))
         .unwrap();
-
     assert_eq!(
         String::from_utf8(md.into_inner()).unwrap(),
-        "test paragraph append\n\
-        \n"
+        "$$\n\\int_0^1 x\\,dx\n$$\n\n>$$\n>a\n>b\n>$$\n\n"
     );
 }
 //endregion
 
-//region String
+//region ThematicBreak
 #[test]
-fn string() {
+fn thematic_break() {
     let mut md = Markdown::new(Vec::new());
-    md.write("test string").unwrap();
+    md.write("above").unwrap();
+    md.write(ThematicBreak).unwrap();
+    md.write("below").unwrap();
     assert_eq!(
         String::from_utf8(md.into_inner()).unwrap(),
-        "test string\n\
-        \n"
+        "above\n\n---\n\nbelow\n\n"
     );
 }
 
 #[test]
-fn string_escaping() {
+fn thematic_break_in_list() {
     let mut md = Markdown::new(Vec::new());
-    md.write("\\test\\string\\").unwrap();
+    md.write(List::new(false).item("item").item(ThematicBreak))
+        .unwrap();
     assert_eq!(
         String::from_utf8(md.into_inner()).unwrap(),
-        "\\\\test\\\\string\\\\\n\
-        \n"
+        "   * item\n   * ---"
     );
 }
-//endregion
 
-//region Link
 #[test]
-fn link() {
-    let mut md = Markdown::new(Vec::new());
-    md.write("test link".link_to("https://test.url")).unwrap();
+fn thematic_break_in_quote() {
+    let quote = Quote::new()
+        .append("above".paragraph())
+        .append(ThematicBreak);
+    assert_eq!(to_markdown_string(quote), ">above\n>\n> ---\n\n");
     assert_eq!(
-        String::from_utf8(md.into_inner()).unwrap(),
-        "[test link](https://test\\.url)\n"
+        to_markdown_string(Quote::new().append(ThematicBreak)),
+        "> ---\n\n"
     );
 }
+//endregion
 
+//region BlankLine
 #[test]
-fn link_escaping() {
+fn blank_line() {
     let mut md = Markdown::new(Vec::new());
-    md.write("[][]test [] link[][]".link_to("https://test().url()"))
-        .unwrap();
+    md.write(BlankLine).unwrap();
+    md.write("a").unwrap();
+    md.write(BlankLine).unwrap();
+    md.write(BlankLine).unwrap();
+    md.write("b").unwrap();
     assert_eq!(
         String::from_utf8(md.into_inner()).unwrap(),
-        "[\\[\\]\\[\\]test \\[\\] link\\[\\]\\[\\]](https://test\\(\\)\\.url\\(\\))\n"
+        "a\n\n\n\nb\n\n"
     );
 }
 
 #[test]
-fn link_append() {
+fn blank_line_in_document() {
+    let document = Document::new()
+        .append("a".paragraph())
+        .append(BlankLine)
+        .append("b".paragraph())
+        .append(BlankLine);
+    assert_eq!(to_markdown_string(document), "a\n\n\nb\n\n");
+}
+
+#[test]
+fn blank_line_in_quote() {
+    let quote = Quote::new()
+        .append("a".paragraph())
+        .append(BlankLine)
+        .append("b".paragraph());
+    assert_eq!(to_markdown_string(quote), ">a\n>\n>\n>b\n\n");
+}
+//endregion
+
+//region Details
+#[test]
+fn details() {
     let mut md = Markdown::new(Vec::new());
     md.write(
-        "test link"
-            .link_to("https://test.url")
-            .append(" ")
-            .append("appended"),
+        Details::new("More ".paragraph().append("info".bold()))
+            .append("Hidden *text*".paragraph())
+            .append(List::new(false).item("a").item("b")),
     )
     .unwrap();
     assert_eq!(
         String::from_utf8(md.into_inner()).unwrap(),
-        "[test link appended](https://test\\.url)\n"
+        "<details><summary>More **info**</summary>\n\
+        \n\
+        Hidden \\*text\\*\n\
+        \n   \
+        * a\n   \
+        * b\n\
+        \n\
+        </details>\n\
+        \n"
     );
 }
 //endregion
 
-//region RichText
+//region Other
 #[test]
-fn code() {
+fn link_as_heading() {
     let mut md = Markdown::new(Vec::new());
-    md.write("co`````de".code()).unwrap();
+    md.write("test link".link_to("https://test.url").heading(2))
+        .unwrap();
     assert_eq!(
         String::from_utf8(md.into_inner()).unwrap(),
-        "`````` co`````de ``````\n\n"
+        "## [test link](https://test.url)\n"
     );
 }
 
 #[test]
-fn bold() {
+fn bold_link() {
     let mut md = Markdown::new(Vec::new());
-    md.write("bo****ld".bold()).unwrap();
+    md.write("bold link".bold().link_to("https://bold"))
+        .unwrap();
     assert_eq!(
         String::from_utf8(md.into_inner()).unwrap(),
-        "**bo\\*\\*\\*\\*ld**\n\n"
+        "[**bold link**](https://bold)\n"
     );
 }
 
 #[test]
-fn italic() {
-    let mut md = Markdown::new(Vec::new());
-    md.write("ita**lic".italic()).unwrap();
+fn try_conversions() {
+    assert!(List::new(false).item("a").try_heading(1).is_err());
+    assert!("link".link_to("https://x").try_bold().is_err());
     assert_eq!(
-        String::from_utf8(md.into_inner()).unwrap(),
-        "*ita\\*\\*lic*\n\n"
+        "text".try_heading(7).err(),
+        Some(ValidationError::HeadingLevel(7))
     );
+    let heading = "text".try_bold().unwrap().try_heading(2).unwrap();
+    assert_eq!(to_markdown_string(heading), "## **text**\n");
 }
 
 #[test]
-fn bold_italic() {
-    let mut md = Markdown::new(Vec::new());
-    md.write("bold italic".italic().bold()).unwrap();
-    assert_eq!(
-        String::from_utf8(md.into_inner()).unwrap(),
-        "***bold italic***\n\n"
-    );
+fn counting_writer() {
+    let doc = Document::new()
+        .append("Title".heading(1))
+        .append("text with *stars* and ".paragraph().append("bold".bold()))
+        .append(List::new(true).item("one").item("two".link_to("https://x")))
+        .append(CodeBlock::new("let x = 1;\n").language("rust"))
+        .append("quoted".quote());
+    let mut md = Markdown::new(CountingWriter::new());
+    md.write(&doc).unwrap();
+    md.write("after").unwrap();
+    let mut expected = Markdown::new(Vec::new());
+    expected.write(&doc).unwrap();
+    expected.write("after").unwrap();
+    assert_eq!(md.into_inner().count(), expected.into_inner().len());
+}
 
-    let mut md = Markdown::new(Vec::new());
-    md.write("bold italic".bold().italic()).unwrap();
+#[test]
+fn visitor_heading_texts() {
+    struct Headings(Vec<String>);
+
+    impl Visitor for Headings {
+        fn visit_heading(&mut self, heading: &Heading) {
+            self.0.push(heading.text());
+        }
+    }
+
+    let document = Document::new()
+        .append("Title".heading(1))
+        .append("text".paragraph())
+        .append(Quote::new().append(Document::new().append("Quoted *heading*".heading(2))))
+        .append(
+            Details::new("More").append(Heading::new(3).append("Hidden".bold()).append(" one")),
+        );
+    let mut headings = Headings(Vec::new());
+    document.accept(&mut headings);
     assert_eq!(
-        String::from_utf8(md.into_inner()).unwrap(),
-        "***bold italic***\n\n"
+        headings.0,
+        vec!["Title", "Quoted \\*heading\\*", "**Hidden** one"]
     );
 }
 
 #[test]
-fn bold_italic_code() {
-    let mut md = Markdown::new(Vec::new());
-    md.write("bold italic code".italic().bold().code()).unwrap();
-    assert_eq!(
-        String::from_utf8(md.into_inner()).unwrap(),
-        "***` bold italic code `***\n\n"
-    );
+fn backticks() {
+    assert_eq!(backticks_needed("plain"), 1);
+    assert_eq!(backticks_needed("a `tick`"), 2);
+    assert_eq!(backticks_needed("``` ``` ```"), 4);
+    assert_eq!(backticks_needed("ends with ``"), 3);
+}
 
+#[test]
+fn boxed_element() {
+    let heading: Box<dyn MarkdownWritable> = Box::new("Boxed".heading(2));
     let mut md = Markdown::new(Vec::new());
-    md.write("bold italic".bold().italic()).unwrap();
+    md.write(heading).unwrap();
+    let elements: Vec<Box<dyn MarkdownWritable>> = vec![Box::new("a".bold()), Box::new(" b")];
+    md.write(Paragraph::new().append_all(elements)).unwrap();
     assert_eq!(
         String::from_utf8(md.into_inner()).unwrap(),
-        "***bold italic***\n\n"
+        "## Boxed\n\n**a** b\n\n"
     );
 }
 
+/// Asserts that `element` renders the same when written by reference
+fn assert_reference_renders_same<T: MarkdownWritable>(element: T) {
+    assert_eq!(to_markdown_string(&element), to_markdown_string(element));
+}
+
 #[test]
-fn asterisk_escaping() {
-    let mut md = Markdown::new(Vec::new());
-    md.write("test **".bold()).unwrap();
-    assert_eq!(
-        String::from_utf8(md.into_inner()).unwrap(),
-        "**test \\*\\***\n\n"
-    );
+fn reference_renders_same() {
+    let headings = ["Heading".heading(2)];
+    assert_reference_renders_same(TableOfContents::from_headings(&headings));
+    let [heading] = headings;
+    assert_reference_renders_same(heading);
+    assert_reference_renders_same(FrontMatter::new().field("title", "*a*"));
+    assert_reference_renders_same(Document::new().append("*a*").append("b".heading(1)));
+    assert_reference_renders_same("*a*".paragraph().append("b".italic()));
+    assert_reference_renders_same(Span::new().append("a").append("b".bold()));
+    assert_reference_renders_same("text".link_to("https://e.com").title("t"));
+    assert_reference_renders_same(Autolink::new("https://e.com"));
+    let references = ReferenceTable::new();
+    assert_reference_renders_same(references.link("https://e.com").append("a"));
+    assert_reference_renders_same(&references);
+    assert_reference_renders_same(Image::new("a.png").alt("alt"));
+    assert_reference_renders_same("rich".bold().italic().code());
+    assert_reference_renders_same(List::new(true).title("t").item("a").item("b"));
+    assert_reference_renders_same(Quote::new().append("a").append(Quote::new().append("b")));
+    assert_reference_renders_same(CodeBlock::new("let a = 1;").language("rust"));
+    assert_reference_renders_same(HtmlBlock::new("<div>\n</div>"));
+    assert_reference_renders_same(Comment::new("note"));
+    assert_reference_renders_same(InlineHtml::new("<br>"));
+    assert_reference_renders_same(Raw("**a**"));
+    assert_reference_renders_same(ThematicBreak);
+    assert_reference_renders_same("a".paragraph().append(LineBreak).append("b"));
+    assert_reference_renders_same(SoftBreak);
+    assert_reference_renders_same(BlankLine);
+    assert_reference_renders_same(Trusted::new("*a*".paragraph()));
+    assert_reference_renders_same(Details::new("summary").append("body"));
+    assert_reference_renders_same(String::from("*a*"));
+    assert_reference_renders_same(Cow::Borrowed("*a*"));
 }
-//endregion
 
-//region List
 #[test]
-fn list() {
-    let mut md = Markdown::new(Vec::new());
-    md.write(
-        List::new(true).item("item 1").item("bold".bold()).item(
-            List::new(false)
-                .title("nested list")
-                .item("bold".bold().paragraph().append("italic".italic())),
-        ),
-    )
-    .unwrap();
-    assert_eq!(
-        String::from_utf8(md.into_inner()).unwrap(),
-        "\n   1. item 1\n   1. **bold**\n   1. nested list\n      * **bold***italic*"
+fn reference_renders_same_table() {
+    assert_reference_renders_same(
+        Table::new(true)
+            .header(vec!["a", "b"])
+            .row(TableRow::new().cell("1").cell("2")),
     );
 }
-//endregion
 
-//region Table
+#[cfg(feature = "gfm")]
 #[test]
-fn table() {
-    let mut md = Markdown::new(Vec::new());
-    md.write(
-        Table::new(true)
-            .header(vec!["Header1", "Header2", "Header3"])
-            .rows(vec![vec!["row01".to_string(), "row02".to_string(), "row03".to_string()]]),
-    )
-    .unwrap();
+fn reference_renders_same_gfm() {
+    let footnote = Footnote::new("1").append("note");
+    assert_reference_renders_same(footnote.reference());
+    assert_reference_renders_same(footnote);
+}
+
+#[test]
+fn reference_renders_same_math() {
+    assert_reference_renders_same(Math::new("a^2"));
+    assert_reference_renders_same(MathBlock::new("a^2"));
+}
+
+#[test]
+fn custom_element() {
+    struct Marked<'a>(&'a str);
+
+    impl MarkdownWritable for Marked<'_> {
+        fn write_to(&self, ctx: &mut RenderContext) -> Result<(), std::io::Error> {
+            use std::io::Write;
+            ctx.write_all(b"==")?;
+            self.0.write_to(&mut ctx.child())?;
+            ctx.write_all(b"==")
+        }
+
+        fn count_max_streak(&self, char: u8, carry: usize) -> (usize, usize) {
+            self.0.count_max_streak(char, carry)
+        }
+    }
 
     assert_eq!(
-        String::from_utf8(md.into_inner()).unwrap(),
-        "<table><thead><tr><th>Header1</th><th>Header2</th><th>Header3</th></tr></thead>\
-        <tbody><tr><td>row01</td><td>row02</td><td>row03</td></tr></tbody></table>\n"
+        to_markdown_string(Quote::new().append("> ".paragraph().append(Marked("a*b")))),
+        ">\\> ==a\\*b==\n\n"
     );
 }
-//endregion
 
-//region Quote
 #[test]
-fn quote() {
+fn set_writer() {
     let mut md = Markdown::new(Vec::new());
-    md.write("bold quote".bold().quote()).unwrap();
-    md.write("code quote".code().quote()).unwrap();
-    md.write("test ".quote().append("link".link_to("sample.url")))
+    md.write("first".heading(1)).unwrap();
+    let first = md.set_writer(Vec::new());
+    md.write("second".heading(2)).unwrap();
+    assert_eq!(String::from_utf8(first).unwrap(), "# first\n");
+    assert_eq!(String::from_utf8(md.into_inner()).unwrap(), "## second\n");
+}
+
+#[test]
+fn trim_trailing_whitespace() {
+    let mut md = Markdown::new(Vec::new()).trim_trailing_whitespace(true);
+    md.write("broken".paragraph().append(LineBreak).append("line   "))
         .unwrap();
     md.write(
-        List::new(true)
-            .title("quoted list")
-            .item("item")
-            .item(
-                List::new(false)
-                    .title("nested quoted list")
-                    .item("bold item quote".bold().quote())
-                    .item("test".link_to("sample.url")),
-            )
-            .quote(),
+        List::new(false).item(
+            Document::new()
+                .append("first".paragraph())
+                .append("second".paragraph()),
+        ),
     )
     .unwrap();
-    assert_eq!(
-        String::from_utf8(md.into_inner()).unwrap(),
-        "\n>**bold quote**\n\n\n>` code quote `\n\n\n>test [link](sample\\.url)\n\n\n>quoted list\n>   1. item\n>   1. nested quoted list\n>      * >**bold item quote**\n>      * [test](sample\\.url)\n\n"
-    );
+    #[cfg(feature = "gfm")]
+    md.write(
+        Quote::new()
+            .alert(AlertKind::Note)
+            .append("first".paragraph())
+            .append("second".paragraph()),
+    )
+    .unwrap();
+    let output = String::from_utf8(md.into_inner()).unwrap();
+    assert!(output.starts_with("broken  \nline\n\n   * first\n\n     second"));
+    #[cfg(feature = "gfm")]
+    assert!(output.ends_with("> [!NOTE]\n> first\n>\n> second\n\n"));
+    assert!(output
+        .lines()
+        .all(|line| line == "broken  " || !line.ends_with(' ')));
 }
-//endregion
 
-//region Other
 #[test]
-fn link_as_heading() {
-    let mut md = Markdown::new(Vec::new());
-    md.write("test link".link_to("https://test.url").heading(2))
-        .unwrap();
-    assert_eq!(
-        String::from_utf8(md.into_inner()).unwrap(),
-        "## [test link](https://test\\.url)\n"
-    );
+fn markdown_string() {
+    assert_eq!(to_markdown_string("test".heading(1)), "# test\n");
+    assert_eq!(to_markdown_string("bold".bold()), "**bold**\n\n");
 }
 
 #[test]
-fn bold_link() {
-    let mut md = Markdown::new(Vec::new());
-    md.write("bold link".bold().link_to("https://bold"))
-        .unwrap();
-    assert_eq!(
-        String::from_utf8(md.into_inner()).unwrap(),
-        "[**bold link**](https://bold)\n"
-    );
+fn display() {
+    let paragraph = "a*b".paragraph().append("c".bold());
+    assert_eq!(format!("{}", paragraph.display()), "a\\*b**c**\n\n");
+    assert_eq!(format!("[{}]", "x".link_to("y").display()), "[[x](y)\n]");
 }
 
 #[test]
@@ -327,7 +2879,276 @@ fn unicode() {
     .unwrap();
     assert_eq!(
         String::from_utf8(md.into_inner()).unwrap(),
-        "***` 뜲漜ֵٰ𷸞ڡ򬻵y콰񍋋ȱ擥񲇧ۼ򠝊₧☾y굻瘲놶􋄻ᘝmā򞛥~ݳ奂ҳu `***\n\n"
+        "**_` 뜲漜ֵٰ𷸞ڡ򬻵y콰񍋋ȱ擥񲇧ۼ򠝊₧☾y굻瘲놶􋄻ᘝmā򞛥~ݳ奂ҳu `_**\n\n"
+    );
+}
+//endregion
+
+//region Parsing
+/// Renders `element` and asserts that a CommonMark parser reads `expected`
+///
+/// Consecutive text events are merged, as escapes split the text.
+fn assert_renders_as<T: MarkdownWritable>(element: T, expected: &[Event]) {
+    assert_parses_as(element, Options::empty(), expected);
+}
+
+/// Like [assert_renders_as], with the parser extensions in `options` enabled
+fn assert_parses_as<T: MarkdownWritable>(element: T, options: Options, expected: &[Event]) {
+    let rendered = to_markdown_string(element);
+    let mut events: Vec<Event> = Vec::new();
+    for event in Parser::new_ext(&rendered, options) {
+        match (events.last_mut(), event) {
+            (Some(Event::Text(text)), Event::Text(next)) => {
+                *text = CowStr::from(format!("{}{}", text, next));
+            }
+            (_, event) => events.push(event),
+        }
+    }
+    assert_eq!(events, expected, "rendered as {:?}", rendered);
+}
+
+fn paragraph_events(inner: Vec<Event<'static>>) -> Vec<Event<'static>> {
+    let mut events = vec![Event::Start(Tag::Paragraph)];
+    events.extend(inner);
+    events.push(Event::End(TagEnd::Paragraph));
+    events
+}
+
+#[test]
+fn parsed_code_spans() {
+    for code in ["a`b", "``", "`a", "a``", " ` ", "a```b``c"].iter() {
+        assert_renders_as(
+            code.code().paragraph(),
+            &paragraph_events(vec![Event::Code(CowStr::from(*code))]),
+        );
+    }
+}
+
+#[test]
+fn parsed_link_destinations() {
+    let addresses = [
+        "https://e.com/a_(b)",
+        "https://e.com/a)b",
+        "https://e.com/(a",
+        "https://e.com/a b",
+        "https://e.com/a<b>",
+    ];
+    for address in addresses.iter() {
+        assert_renders_as(
+            "x".link_to(address).paragraph(),
+            &paragraph_events(vec![
+                Event::Start(Tag::Link {
+                    link_type: LinkType::Inline,
+                    dest_url: CowStr::from(*address),
+                    title: CowStr::from(""),
+                    id: CowStr::from(""),
+                }),
+                Event::Text(CowStr::from("x")),
+                Event::End(TagEnd::Link),
+            ]),
+        );
+    }
+}
+
+#[test]
+fn entities() {
+    assert_eq!(
+        to_markdown_string("AT&amp;T &#38; &#x26; a=1&b=2 & c;".paragraph()),
+        "AT\\&amp;T \\&\\#38; \\&\\#x26; a=1&b=2 & c;\n\n"
+    );
+}
+
+#[test]
+fn parsed_entities() {
+    for text in ["AT&amp;T", "&#38;", "&#x26;", "a=1&b=2", "&"].iter() {
+        assert_renders_as(
+            text.paragraph(),
+            &paragraph_events(vec![Event::Text(CowStr::from(*text))]),
+        );
+    }
+    assert_renders_as(
+        Paragraph::new().append('&').append("amp;"),
+        &paragraph_events(vec![Event::Text(CowStr::from("&amp;"))]),
+    );
+}
+
+#[test]
+fn parsed_text() {
+    let text = "*a* _b_ [c](d) ![e] `f` # h! 1. i + j \\";
+    assert_renders_as(
+        text.paragraph(),
+        &paragraph_events(vec![Event::Text(CowStr::from(text))]),
+    );
+}
+
+#[test]
+fn parsed_table() {
+    use pulldown_cmark::Alignment as Align;
+
+    let text = |text: &'static str| Event::Text(CowStr::from(text));
+    let cells = |row: Vec<Event<'static>>| {
+        row.into_iter().flat_map(|cell| {
+            vec![
+                Event::Start(Tag::TableCell),
+                cell,
+                Event::End(TagEnd::TableCell),
+            ]
+        })
+    };
+    let table = Table::new(false)
+        .header(vec!["a|b", "*c*"])
+        .alignments(vec![Alignment::Left, Alignment::Right])
+        .row(TableRow::new().cell("1 | 2").cell("x".bold()));
+    let mut expected = vec![
+        Event::Start(Tag::Table(vec![Align::Left, Align::Right])),
+        Event::Start(Tag::TableHead),
+    ];
+    expected.extend(cells(vec![text("a|b"), text("*c*")]));
+    expected.extend(vec![
+        Event::End(TagEnd::TableHead),
+        Event::Start(Tag::TableRow),
+    ]);
+    expected.extend(cells(vec![text("1 | 2")]));
+    expected.extend(vec![
+        Event::Start(Tag::TableCell),
+        Event::Start(Tag::Strong),
+        text("x"),
+        Event::End(TagEnd::Strong),
+        Event::End(TagEnd::TableCell),
+        Event::End(TagEnd::TableRow),
+        Event::End(TagEnd::Table),
+    ]);
+    assert_parses_as(table, Options::ENABLE_TABLES, &expected);
+}
+
+#[test]
+fn bang_before_link() {
+    let paragraph = Paragraph::new()
+        .append("Wow!")
+        .append("docs".link_to("https://e.com"));
+    assert_eq!(
+        to_markdown_string(paragraph),
+        "Wow\\![docs](https://e.com)\n\n"
+    );
+    assert_eq!(to_markdown_string("Wow!".paragraph()), "Wow!\n\n");
+    assert_eq!(
+        to_markdown_string("Wow!".paragraph().append(" [x]")),
+        "Wow! \\[x\\]\n\n"
+    );
+}
+
+#[test]
+fn parsed_bang_before_link() {
+    let events = paragraph_events(vec![
+        Event::Text(CowStr::from("Wow!")),
+        Event::Start(Tag::Link {
+            link_type: LinkType::Inline,
+            dest_url: CowStr::from("https://e.com"),
+            title: CowStr::from(""),
+            id: CowStr::from(""),
+        }),
+        Event::Text(CowStr::from("docs")),
+        Event::End(TagEnd::Link),
+    ]);
+    let link = || "docs".link_to("https://e.com");
+    assert_renders_as(Paragraph::new().append("Wow!").append(link()), &events);
+    assert_renders_as(
+        Paragraph::new()
+            .append(Span::new().append("Wow!"))
+            .append(Span::new().append(link())),
+        &events,
+    );
+}
+
+#[test]
+fn parsed_split_list_number() {
+    for (number, rest) in [
+        ("1", ". item"),
+        ("1", ") item"),
+        ("1", "2. item"),
+        ("- ", "+ item"),
+    ]
+    .iter()
+    {
+        let expected = paragraph_events(vec![Event::Text(CowStr::from(format!(
+            "{}{}",
+            number, rest
+        )))]);
+        assert_renders_as(Paragraph::new().append(*number).append(*rest), &expected);
+        assert_renders_as(
+            Paragraph::new().wrap(80).append(*number).append(*rest),
+            &expected,
+        );
+    }
+    assert_renders_as(
+        Quote::new().append("1").append(". quoted"),
+        &[
+            Event::Start(Tag::BlockQuote(None)),
+            Event::Start(Tag::Paragraph),
+            Event::Text(CowStr::from("1. quoted")),
+            Event::End(TagEnd::Paragraph),
+            Event::End(TagEnd::BlockQuote(None)),
+        ],
+    );
+}
+
+#[test]
+fn parsed_list_indent() {
+    let list = List::new(false).indent(4).item(
+        List::new(true)
+            .indent(4)
+            .title("a")
+            .item(List::new(false).indent(4).title("b").item("c")),
+    );
+    let text = |text: &'static str| Event::Text(CowStr::from(text));
+    assert_renders_as(
+        list,
+        &[
+            Event::Start(Tag::List(None)),
+            Event::Start(Tag::Item),
+            text("a"),
+            Event::Start(Tag::List(Some(1))),
+            Event::Start(Tag::Item),
+            text("b"),
+            Event::Start(Tag::List(None)),
+            Event::Start(Tag::Item),
+            text("c"),
+            Event::End(TagEnd::Item),
+            Event::End(TagEnd::List(false)),
+            Event::End(TagEnd::Item),
+            Event::End(TagEnd::List(true)),
+            Event::End(TagEnd::Item),
+            Event::End(TagEnd::List(false)),
+        ],
+    );
+}
+
+#[test]
+fn parsed_nested_quote() {
+    let quote = Quote::new().append("outer").append(LineBreak).append(
+        Quote::new().append(
+            Quote::new()
+                .append("first")
+                .append(LineBreak)
+                .append("second"),
+        ),
     );
+    let mut expected = vec![Event::Start(Tag::BlockQuote(None))];
+    expected.extend(paragraph_events(vec![Event::Text(CowStr::from("outer"))]));
+    expected.extend(vec![
+        Event::Start(Tag::BlockQuote(None)),
+        Event::Start(Tag::BlockQuote(None)),
+    ]);
+    expected.extend(paragraph_events(vec![
+        Event::Text(CowStr::from("first")),
+        Event::HardBreak,
+        Event::Text(CowStr::from("second")),
+    ]));
+    expected.extend(vec![
+        Event::End(TagEnd::BlockQuote(None)),
+        Event::End(TagEnd::BlockQuote(None)),
+        Event::End(TagEnd::BlockQuote(None)),
+    ]);
+    assert_renders_as(quote, &expected);
 }
 //endregion
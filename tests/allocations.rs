@@ -0,0 +1,88 @@
+//! Allocation counts of the public API
+//!
+//! Kept in its own test binary, so the counting allocator doesn't replace the
+//! system allocator of the unit tests.
+use markdown_gen::markdown::{to_markdown_string, AsMarkdown, Markdown, MarkdownWritable};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    static LIVE_BYTES: Cell<isize> = const { Cell::new(0) };
+    static PEAK_BYTES: Cell<isize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        let _ = LIVE_BYTES.try_with(|live| {
+            live.set(live.get() + layout.size() as isize);
+            let _ = PEAK_BYTES.try_with(|peak| peak.set(peak.get().max(live.get())));
+        });
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let _ = LIVE_BYTES.try_with(|live| live.set(live.get() - layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+/// Resets the peak to the current live bytes and returns it
+fn reset_peak_bytes() -> isize {
+    let live = LIVE_BYTES.with(Cell::get);
+    PEAK_BYTES.with(|peak| peak.set(live));
+    live
+}
+
+fn peak_bytes() -> isize {
+    PEAK_BYTES.with(Cell::get)
+}
+
+#[test]
+fn text_fragments_are_not_boxed() {
+    let before = allocations();
+    let mut paragraph = "start".paragraph();
+    for _ in 0..10_000 {
+        paragraph = paragraph.append("fragment");
+    }
+    let used = allocations() - before;
+    assert!(used < 100, "{} allocations for 10k fragments", used);
+
+    let paragraph = paragraph.append("bold".bold()).append(" end");
+    let rendered = to_markdown_string(paragraph);
+    assert!(rendered.starts_with("startfragmentfragment"));
+    assert!(rendered.ends_with("fragment**bold** end\n\n"));
+}
+
+#[test]
+fn count_max_streak_does_not_allocate() {
+    let code = "`x".repeat(100_000) + "````";
+    let before = allocations();
+    assert_eq!(code.count_max_streak(b'`', 0), (1, 4));
+    assert_eq!(code.as_str().count_max_streak(b'`', 2), (3, 4));
+    assert_eq!(allocations(), before);
+}
+
+#[test]
+fn streaming_bounded_memory() {
+    let mut md = Markdown::new(std::io::sink());
+    let before = reset_peak_bytes();
+    for i in 0..100_000 {
+        md.paragraph(|p| {
+            p.text("paragraph ")?;
+            p.write(if i % 2 == 0 { "even" } else { "odd" }.bold())
+        })
+        .unwrap();
+    }
+    assert!(peak_bytes() - before < 1024);
+}